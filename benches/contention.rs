@@ -0,0 +1,83 @@
+//! Benchmarks for the scenarios that decide whether a change to the gate's internals pays off:
+//! waking many parked waiters, toggling rapidly, checking the state in a hot loop, and cloning gates.
+//!
+//! Run with `cargo bench`.
+
+use std::hint::black_box;
+
+use async_gate::{new_lowered, new_raised, Gate};
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use tokio_test::{assert_pending, assert_ready_ok, task::spawn};
+
+/// A single raise with `n` tasks parked on `raised`, including polling every one of them to completion
+fn single_raise_with_parked_waiters(c: &mut Criterion) {
+    let mut group = c.benchmark_group("single_raise_with_parked_waiters");
+
+    for n in [1, 10, 100, 1_000, 10_000] {
+        group.bench_with_input(BenchmarkId::from_parameter(n), &n, |b, &n| {
+            b.iter_batched(
+                || {
+                    let (lever, gate) = new_lowered();
+                    let gates = vec![gate; n];
+
+                    (lever, gates)
+                },
+                |(lever, mut gates)| {
+                    let mut waiters: Vec<_> =
+                        gates.iter_mut().map(|gate| spawn(gate.raised())).collect();
+                    for waiter in &mut waiters {
+                        assert_pending!(waiter.poll());
+                    }
+
+                    lever.raise().unwrap();
+
+                    for waiter in &mut waiters {
+                        assert_ready_ok!(waiter.poll());
+                    }
+                },
+                BatchSize::LargeInput,
+            );
+        });
+    }
+
+    group.finish();
+}
+
+/// Raising and lowering back and forth with one gate watching, so every toggle really changes the state
+fn rapid_toggle_throughput(c: &mut Criterion) {
+    let (lever, _gate) = new_lowered();
+
+    c.bench_function("rapid_toggle_throughput", |b| {
+        b.iter(|| {
+            lever.raise().unwrap();
+            lever.lower().unwrap();
+        });
+    });
+}
+
+/// Checking the state over and over, like a loop polling the gate would
+fn is_raised_hot_loop(c: &mut Criterion) {
+    let (_lever, gate) = new_raised();
+
+    c.bench_function("is_raised_hot_loop", |b| {
+        b.iter(|| black_box(&gate).is_raised());
+    });
+}
+
+/// Cloning a gate and dropping the clone, like handing one to every new connection would
+fn gate_clone(c: &mut Criterion) {
+    let (_lever, gate) = new_raised();
+
+    c.bench_function("gate_clone", |b| {
+        b.iter(|| -> Gate { black_box(&gate).clone() });
+    });
+}
+
+criterion_group!(
+    benches,
+    single_raise_with_parked_waiters,
+    rapid_toggle_throughput,
+    is_raised_hot_loop,
+    gate_clone,
+);
+criterion_main!(benches);
@@ -0,0 +1,92 @@
+use std::{
+    future::{poll_fn, Future},
+    pin::pin,
+    task::Poll,
+};
+
+use thiserror::Error;
+
+use crate::Gate;
+
+/// The gate was lowered before the future finished, so the future was cancelled
+#[derive(Debug, Error)]
+#[error("gate was lowered before the future finished")]
+pub struct LoweredBeforeFinished;
+
+impl Gate {
+    /// Run the `future`, cancelling it (by dropping it) as soon as the gate is lowered,
+    /// for work that should only go on while the gate is raised.
+    /// If the gate is already lowered, the `future` is never polled.
+    ///
+    /// The gate is checked before every poll of the `future`,
+    /// so a lower is noticed before the `future` makes any more progress.
+    /// If the lever is dropped while the gate is raised, the `future` runs to completion.
+    /// # Errors
+    /// If the gate is lowered before the `future` finishes, an `Err` is returned.
+    /// # Cancel safety
+    /// This method is as cancel safe as the `future` is.
+    pub async fn abort_when_lowered<F: Future>(
+        &mut self,
+        future: F,
+    ) -> Result<F::Output, LoweredBeforeFinished> {
+        let mut lowered = pin!(self.lowered());
+        let mut lever_dropped = false;
+        let mut future = pin!(future);
+
+        poll_fn(|cx| {
+            if !lever_dropped {
+                match lowered.as_mut().poll(cx) {
+                    Poll::Ready(Ok(())) => return Poll::Ready(Err(LoweredBeforeFinished)),
+                    // The gate will never be lowered, so there's nothing left to watch for
+                    Poll::Ready(Err(_)) => lever_dropped = true,
+                    Poll::Pending => {}
+                }
+            }
+
+            future.as_mut().poll(cx).map(Ok)
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio_test::{assert_pending, assert_ready, task};
+
+    use crate::{new_lowered, new_raised};
+
+    /// Tests that the future is cancelled once the gate is lowered.
+    #[test]
+    fn cancels_once_lowered() {
+        let (lever, mut gate) = new_raised();
+        let (sender, receiver) = tokio::sync::oneshot::channel::<()>();
+
+        let mut running = task::spawn(gate.abort_when_lowered(receiver));
+        assert_pending!(running.poll());
+
+        lever.lower().unwrap();
+        assert!(assert_ready!(running.poll()).is_err());
+        drop(running);
+        assert!(sender.is_closed());
+    }
+
+    /// Tests that the future runs to completion if the lever is dropped while the gate is raised,
+    /// and never runs if the gate is already lowered.
+    #[test]
+    fn runs_unless_lowered() {
+        let (lever, mut gate) = new_raised();
+        let (sender, receiver) = tokio::sync::oneshot::channel();
+
+        let mut running = task::spawn(gate.abort_when_lowered(receiver));
+        assert_pending!(running.poll());
+
+        drop(lever);
+        assert_pending!(running.poll());
+
+        sender.send(3).unwrap();
+        assert_eq!(assert_ready!(running.poll()).unwrap().unwrap(), 3);
+
+        let (_lever, mut gate) = new_lowered();
+        assert!(assert_ready!(task::spawn(gate.abort_when_lowered(async {})).poll()).is_err());
+    }
+}
@@ -0,0 +1,135 @@
+use std::fmt;
+
+// Under `--cfg loom`, the atomic comes from `loom` so that tests/loom.rs can model check this type
+#[cfg(loom)]
+use loom::sync::atomic::{AtomicBool, Ordering};
+#[cfg(not(loom))]
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::{Gateway, Lowered, Raised};
+
+/// A [`Gateway`] that can be shared between threads and changed without locking,
+/// with the same operations as the standard library's atomic types.
+///
+/// This has nothing to do with [`Gate`](crate::Gate) and [`Lever`](crate::Lever)
+/// and can be used on its own wherever a raised / lowered state is shared purely atomically.
+pub struct AtomicGateway {
+    raised: AtomicBool,
+}
+
+#[inline]
+fn to_raised(gateway: Gateway) -> bool {
+    matches!(gateway, Raised)
+}
+
+#[inline]
+fn from_raised(raised: bool) -> Gateway {
+    if raised {
+        Raised
+    } else {
+        Lowered
+    }
+}
+
+impl AtomicGateway {
+    /// Create a new `AtomicGateway` holding the given `gateway`.
+    #[must_use]
+    pub fn new(gateway: Gateway) -> Self {
+        Self {
+            raised: AtomicBool::new(to_raised(gateway)),
+        }
+    }
+
+    /// Load the current gateway.
+    /// `order` works the same as in [`AtomicBool::load`].
+    #[must_use]
+    pub fn load(&self, order: Ordering) -> Gateway {
+        from_raised(self.raised.load(order))
+    }
+
+    /// Store the given `gateway`.
+    /// `order` works the same as in [`AtomicBool::store`].
+    pub fn store(&self, gateway: Gateway, order: Ordering) {
+        self.raised.store(to_raised(gateway), order);
+    }
+
+    /// Store the given `gateway`, returning the previous one.
+    /// `order` works the same as in [`AtomicBool::swap`].
+    pub fn swap(&self, gateway: Gateway, order: Ordering) -> Gateway {
+        from_raised(self.raised.swap(to_raised(gateway), order))
+    }
+
+    /// Store the `new` gateway if the current one is `current`.
+    /// `success` and `failure` work the same as in [`AtomicBool::compare_exchange`].
+    /// # Errors
+    /// If the current gateway wasn't `current`, nothing is stored and the current gateway is returned in an `Err`.
+    pub fn compare_exchange(
+        &self,
+        current: Gateway,
+        new: Gateway,
+        success: Ordering,
+        failure: Ordering,
+    ) -> Result<Gateway, Gateway> {
+        self.raised
+            .compare_exchange(to_raised(current), to_raised(new), success, failure)
+            .map(from_raised)
+            .map_err(from_raised)
+    }
+
+    /// Flip the current gateway (raised to lowered or lowered to raised), returning the previous one.
+    /// `order` works the same as in [`AtomicBool::fetch_xor`].
+    pub fn fetch_not(&self, order: Ordering) -> Gateway {
+        from_raised(self.raised.fetch_xor(true, order))
+    }
+
+    /// Consume the atomic and return the gateway it held.
+    #[must_use]
+    pub fn into_inner(self) -> Gateway {
+        from_raised(self.raised.into_inner())
+    }
+}
+
+impl From<Gateway> for AtomicGateway {
+    fn from(gateway: Gateway) -> Self {
+        Self::new(gateway)
+    }
+}
+
+impl fmt::Debug for AtomicGateway {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.load(Ordering::Relaxed), f)
+    }
+}
+
+// loom's atomics only work inside `loom::model`
+#[cfg(all(test, not(loom)))]
+mod tests {
+    use super::*;
+
+    /// Tests that `compare_exchange` only stores when the current gateway matches,
+    /// and reports what was there either way.
+    #[test]
+    fn compare_exchange_reports_current() {
+        let atomic = AtomicGateway::new(Lowered);
+
+        assert_eq!(
+            atomic.compare_exchange(Lowered, Raised, Ordering::SeqCst, Ordering::SeqCst),
+            Ok(Lowered)
+        );
+        assert_eq!(
+            atomic.compare_exchange(Lowered, Raised, Ordering::SeqCst, Ordering::SeqCst),
+            Err(Raised)
+        );
+        assert_eq!(atomic.load(Ordering::SeqCst), Raised);
+    }
+
+    /// Tests that `swap` and `fetch_not` return the previous gateway.
+    #[test]
+    fn swap_and_fetch_not_return_previous() {
+        let atomic = AtomicGateway::new(Raised);
+
+        assert_eq!(atomic.swap(Lowered, Ordering::SeqCst), Raised);
+        assert_eq!(atomic.fetch_not(Ordering::SeqCst), Lowered);
+        assert_eq!(atomic.into_inner(), Raised);
+    }
+}
@@ -0,0 +1,86 @@
+use std::{
+    future::Future,
+    pin::pin,
+    sync::Arc,
+    task::{Context, Poll, Wake, Waker},
+    thread::{self, Thread},
+};
+
+use crate::{Gate, LeverDroppedWhileLowered, LeverDroppedWhileRaised};
+
+/// Wakes a thread parked in [`block_on`]
+struct Unpark(Thread);
+
+impl Wake for Unpark {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// Run the `future` to completion on this thread, parking it while the future is pending
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let waker = Waker::from(Arc::new(Unpark(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => return output,
+            // Spurious unparks just poll again
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
+impl Gate {
+    /// Block the current thread until the gate is raised, like [`Gate::raised`] but for synchronous code.
+    ///
+    /// This must not be called from async code, since it blocks the whole thread
+    /// (so other tasks running on it would be stuck too).
+    /// # Errors
+    /// If the lever is dropped while the gate is lowered, an `Err` is returned.
+    pub fn blocking_raised(&mut self) -> Result<(), LeverDroppedWhileLowered> {
+        block_on(self.raised())
+    }
+
+    /// Block the current thread until the gate is lowered, like [`Gate::lowered`] but for synchronous code.
+    ///
+    /// This must not be called from async code, since it blocks the whole thread
+    /// (so other tasks running on it would be stuck too).
+    /// # Errors
+    /// If the lever is dropped while the gate is raised, an `Err` is returned.
+    pub fn blocking_lowered(&mut self) -> Result<(), LeverDroppedWhileRaised> {
+        block_on(self.lowered())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{new_lowered, new_raised};
+
+    /// Tests that a thread blocked waiting for a raise is unblocked by a raise from another thread.
+    #[test]
+    fn blocking_raised_waits_for_raise() {
+        let (lever, mut gate) = new_lowered();
+
+        let waiting = std::thread::spawn(move || gate.blocking_raised());
+        std::thread::sleep(Duration::from_millis(10));
+        assert!(!waiting.is_finished());
+
+        lever.raise().unwrap();
+        waiting.join().unwrap().unwrap();
+    }
+
+    /// Tests that blocking for a lower fails once the lever is dropped while raised.
+    #[test]
+    fn blocking_lowered_fails_on_lever_drop() {
+        let (lever, mut gate) = new_raised();
+
+        let waiting = std::thread::spawn(move || gate.blocking_lowered());
+        drop(lever);
+
+        waiting.join().unwrap().unwrap_err();
+    }
+}
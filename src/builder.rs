@@ -0,0 +1,73 @@
+use crate::{Gate, Gateway, Lever, Lowered, State};
+
+/// Configures and creates a [`Gate`] along with its [`Lever`],
+/// for when [`new`](crate::new) and friends don't offer enough options.
+///
+/// ```
+/// use async_gate::{Gate, Raised};
+///
+/// let (lever, gate) = Gate::builder().initially(Raised).build();
+/// assert!(gate.is_raised());
+/// ```
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct GateBuilder {
+    pub(crate) state: State,
+}
+
+impl Default for GateBuilder {
+    fn default() -> Self {
+        Self {
+            state: State::new(Lowered),
+        }
+    }
+}
+
+impl GateBuilder {
+    /// Start configuring a gate with the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the state the gate starts in (lowered if not set).
+    pub fn initially(mut self, initial: Gateway) -> Self {
+        self.state.gateway = initial;
+        self
+    }
+
+    /// Set the state to move the gate to once every lever is dropped (see [`Lever::on_drop`]).
+    pub fn on_lever_drop(mut self, gateway: Gateway) -> Self {
+        self.state.on_drop = Some(gateway);
+        self
+    }
+
+    /// Create the gate and its lever.
+    #[must_use]
+    pub fn build(self) -> (Lever, Gate) {
+        crate::with_state(self.state)
+    }
+}
+
+impl Gate {
+    /// Start configuring a gate with a [`GateBuilder`].
+    pub fn builder() -> GateBuilder {
+        GateBuilder::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Raised;
+
+    /// Tests that built gates start lowered unless told otherwise.
+    #[test]
+    fn builds_in_initial_state() {
+        let (_lever, gate) = Gate::builder().build();
+        assert!(gate.is_lowered());
+
+        let (lever, gate) = Gate::builder().initially(Raised).build();
+        assert!(gate.is_raised());
+        assert!(lever.is_raised().unwrap());
+    }
+}
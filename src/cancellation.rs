@@ -0,0 +1,86 @@
+use tokio_util::sync::CancellationToken;
+
+use crate::Gate;
+
+impl Gate {
+    /// Returns a gate that is lowered until the given `token` is cancelled and raised from then on,
+    /// so code waiting on gates can wait for a shutdown signalled through a token.
+    ///
+    /// Once the gate is raised, its lever is dropped (since it will never be lowered again),
+    /// like with [`Gate::from_future`].
+    ///
+    /// This is only available with the `tokio-util` feature.
+    /// # Panics
+    /// Panics if called outside of a Tokio runtime.
+    #[must_use]
+    pub fn from_cancellation_token(token: CancellationToken) -> Gate {
+        Gate::from_future(async move { token.cancelled().await })
+    }
+
+    /// Returns a token that is cancelled once the gate is lowered (right away if it already is),
+    /// so code that shuts down through a token can be gated off.
+    ///
+    /// If the lever is dropped while the gate is raised, the token is never cancelled by the gate.
+    /// Cancelling the token (or any clone of it) stops it from following the gate.
+    ///
+    /// This is only available with the `tokio-util` feature.
+    /// # Panics
+    /// Panics if called outside of a Tokio runtime.
+    #[must_use]
+    pub fn cancellation_token(&self) -> CancellationToken {
+        let token = CancellationToken::new();
+
+        if self.is_lowered() {
+            token.cancel();
+            return token;
+        }
+
+        let mut gate = self.clone();
+        let cancel = token.clone();
+
+        tokio::spawn(async move {
+            tokio::select! {
+                lowered = gate.lowered() => {
+                    if lowered.is_ok() {
+                        cancel.cancel();
+                    }
+                }
+                () = cancel.cancelled() => {}
+            }
+        });
+
+        token
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio_util::sync::CancellationToken;
+
+    use crate::{new_lowered, new_raised, Gate};
+
+    /// Tests that a gate from a token is raised once the token is cancelled.
+    #[tokio::test]
+    async fn gate_raised_once_cancelled() {
+        let token = CancellationToken::new();
+        let mut gate = Gate::from_cancellation_token(token.clone());
+        assert!(gate.is_lowered());
+
+        token.cancel();
+        gate.raised().await.unwrap();
+    }
+
+    /// Tests that a token from a gate is cancelled once the gate is lowered.
+    #[tokio::test]
+    async fn token_cancelled_once_lowered() {
+        let (lever, gate) = new_raised();
+        let token = gate.cancellation_token();
+        assert!(!token.is_cancelled());
+
+        lever.lower().unwrap();
+        token.cancelled().await;
+
+        let (_lever, gate) = new_lowered();
+        assert!(gate.cancellation_token().is_cancelled());
+    }
+}
@@ -0,0 +1,756 @@
+//! Utilities for waiting on and watching a few gates at once
+
+use std::{
+    future::{poll_fn, Future},
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_core::Stream;
+use thiserror::Error;
+
+use crate::{
+    Edge, Gate, Gateway, LeverDroppedWhileLowered, LeverDroppedWhileRaised, Lowered, Raised,
+};
+
+/// Wait until any of the given `gates` is raised,
+/// returning the index of the first one to be raised (or to fail to) along with its result.
+///
+/// If `gates` is empty, this never finishes.
+/// # Cancel safety
+/// This method is cancel safe, for the same reasons as [`Gate::raised`].
+pub async fn race_raised(gates: &mut [Gate]) -> (usize, Result<(), LeverDroppedWhileLowered>) {
+    let mut waiting: Vec<_> = gates
+        .iter_mut()
+        .map(|gate| Box::pin(gate.raised()))
+        .collect();
+
+    poll_fn(|cx| poll_first_ready(&mut waiting, cx)).await
+}
+
+/// Wait until any of the given `gates` is lowered,
+/// returning the index of the first one to be lowered (or to fail to) along with its result.
+///
+/// If `gates` is empty, this never finishes.
+/// # Cancel safety
+/// This method is cancel safe, for the same reasons as [`Gate::lowered`].
+pub async fn race_lowered(gates: &mut [Gate]) -> (usize, Result<(), LeverDroppedWhileRaised>) {
+    let mut waiting: Vec<_> = gates
+        .iter_mut()
+        .map(|gate| Box::pin(gate.lowered()))
+        .collect();
+
+    poll_fn(|cx| poll_first_ready(&mut waiting, cx)).await
+}
+
+/// Some of the gates' levers were dropped in the other state than the one being waited for,
+/// so the gates can never all be in that state
+#[derive(Debug, Error)]
+#[error("levers of gates {indices:?} were dropped in the other state")]
+pub struct LeversDropped {
+    /// The indices of the gates whose levers were dropped in the other state
+    pub indices: Vec<usize>,
+}
+
+/// Wait until all of the given `gates` are raised at once
+/// (returning right away if there are no `gates`).
+///
+/// Gates that were raised but are lowered again by the time the others are raised are waited for again,
+/// so this only finishes when every gate was seen raised in a single pass over them.
+/// # Errors
+/// If the lever of any gate is dropped while it's lowered, an `Err` listing every such gate is returned.
+/// # Cancel safety
+/// This method is cancel safe, for the same reasons as [`Gate::raised`].
+pub async fn all_raised(gates: &mut [Gate]) -> Result<(), LeversDropped> {
+    loop {
+        for gate in gates.iter_mut() {
+            if gate.raised().await.is_err() {
+                return Err(levers_dropped_in(gates, Lowered));
+            }
+        }
+
+        if gates.iter().all(Gate::is_raised) {
+            return Ok(());
+        }
+    }
+}
+
+/// Wait until all of the given `gates` are lowered at once
+/// (returning right away if there are no `gates`).
+///
+/// Gates that were lowered but are raised again by the time the others are lowered are waited for again,
+/// so this only finishes when every gate was seen lowered in a single pass over them.
+/// # Errors
+/// If the lever of any gate is dropped while it's raised, an `Err` listing every such gate is returned.
+/// # Cancel safety
+/// This method is cancel safe, for the same reasons as [`Gate::lowered`].
+pub async fn all_lowered(gates: &mut [Gate]) -> Result<(), LeversDropped> {
+    loop {
+        for gate in gates.iter_mut() {
+            if gate.lowered().await.is_err() {
+                return Err(levers_dropped_in(gates, Raised));
+            }
+        }
+
+        if gates.iter().all(Gate::is_lowered) {
+            return Ok(());
+        }
+    }
+}
+
+/// Returns which of the `gates` had their lever dropped while in the given `gateway`.
+fn levers_dropped_in(gates: &[Gate], gateway: Gateway) -> LeversDropped {
+    LeversDropped {
+        indices: gates
+            .iter()
+            .enumerate()
+            .filter(|(_, gate)| {
+                gate.lever_was_dropped() && gate.receiver.borrow().gateway == gateway
+            })
+            .map(|(index, _)| index)
+            .collect(),
+    }
+}
+
+fn poll_first_ready<F: Future + ?Sized>(
+    futures: &mut [Pin<Box<F>>],
+    cx: &mut Context<'_>,
+) -> Poll<(usize, F::Output)> {
+    for (index, future) in futures.iter_mut().enumerate() {
+        if let Poll::Ready(output) = future.as_mut().poll(cx) {
+            return Poll::Ready((index, output));
+        }
+    }
+
+    Poll::Pending
+}
+
+/// The wait for a gate's next change, resolving to the gate along with its new state and epoch
+/// (or `None` if the lever was dropped)
+type NextChange = Pin<Box<dyn Future<Output = (Gate, Option<(Gateway, u64)>)> + Send>>;
+
+async fn next_change(mut gate: Gate) -> (Gate, Option<(Gateway, u64)>) {
+    let change = match gate.receiver.changed().await {
+        Ok(()) => {
+            let state = gate.receiver.borrow_and_update();
+            Some((state.gateway, state.epoch))
+        }
+        Err(_) => None,
+    };
+
+    (gate, change)
+}
+
+impl Gate {
+    /// Returns a stream yielding the state the gate moves to whenever it changes from now on.
+    /// The stream ends once the lever has been dropped (after yielding the state it was dropped in, if that was a change).
+    ///
+    /// Like with waiting, a gate that is raised and lowered again
+    /// before the stream gets to look at it doesn't yield anything,
+    /// but [`Changes::missed_transitions`] tells how many transitions were skipped like that.
+    #[must_use]
+    pub fn changes(&self) -> Changes {
+        let mut gate = self.clone();
+        let (last_seen, last_epoch) = {
+            let state = gate.receiver.borrow_and_update();
+            (state.gateway, state.epoch)
+        };
+
+        Changes {
+            last_seen,
+            last_epoch,
+            missed: 0,
+            next: Some(Box::pin(next_change(gate))),
+        }
+    }
+}
+
+/// The stream returned by [`Gate::changes`]
+pub struct Changes {
+    last_seen: Gateway,
+    /// The epoch the gate was last seen in
+    last_epoch: u64,
+    /// How many transitions happened without being yielded
+    missed: u64,
+    /// The wait for the next change, or `None` once the lever was dropped
+    next: Option<NextChange>,
+}
+
+impl Changes {
+    /// Returns how many of the gate's transitions this stream has skipped so far,
+    /// because they were undone before the stream got to look at the gate
+    /// (like a raise followed by a lower, which yields nothing).
+    ///
+    /// When this goes up, anything kept in sync with the gate's state may have missed edges and should resynchronize.
+    /// (Transitions before a [`Gate::reset`] that the stream didn't get to look at aren't counted.)
+    #[must_use]
+    pub fn missed_transitions(&self) -> u64 {
+        self.missed
+    }
+}
+
+impl Stream for Changes {
+    type Item = Gateway;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        while let Some(next) = &mut self.next {
+            let (gate, change) = std::task::ready!(next.as_mut().poll(cx));
+
+            let Some((gateway, epoch)) = change else {
+                self.next = None;
+                break;
+            };
+
+            self.next = Some(Box::pin(next_change(gate)));
+
+            // If the gate was reset, the epoch went backwards
+            // and there's no telling what happened before that, so nothing is counted
+            let transitions = epoch.saturating_sub(self.last_epoch);
+            let yielded = u64::from(gateway != self.last_seen);
+            self.missed += transitions.saturating_sub(yielded);
+            self.last_epoch = epoch;
+
+            if gateway != self.last_seen {
+                self.last_seen = gateway;
+                return Poll::Ready(Some(gateway));
+            }
+        }
+
+        Poll::Ready(None)
+    }
+}
+
+impl Gate {
+    /// Returns a stream yielding every time the gate is raised from now on,
+    /// as the [epoch](Gate::current_epoch) it was raised in.
+    /// The stream ends once the lever has been dropped.
+    ///
+    /// Unlike waiting or [`Gate::changes`], this doesn't miss raises that were undone before the stream got to look at the gate:
+    /// it works them out from how far the epoch moved.
+    /// (Raises before a [`Gate::reset`] that the stream didn't get to look at are still missed.)
+    #[must_use]
+    pub fn each_raise(&self) -> Edges {
+        Edges::new(self, Raised)
+    }
+
+    /// Returns a stream yielding every time the gate is lowered from now on,
+    /// as the [epoch](Gate::current_epoch) it was lowered in.
+    /// The stream ends once the lever has been dropped.
+    ///
+    /// Unlike waiting or [`Gate::changes`], this doesn't miss lowers that were undone before the stream got to look at the gate:
+    /// it works them out from how far the epoch moved.
+    /// (Lowers before a [`Gate::reset`] that the stream didn't get to look at are still missed.)
+    #[must_use]
+    pub fn each_lower(&self) -> Edges {
+        Edges::new(self, Lowered)
+    }
+}
+
+/// The stream returned by [`Gate::each_raise`] and [`Gate::each_lower`]
+pub struct Edges {
+    /// The state whose every edge is yielded
+    target: Gateway,
+    /// The last epoch that was considered for yielding, and the gate's state in it
+    cursor: (u64, Gateway),
+    /// The latest epoch the gate was seen in
+    latest: u64,
+    /// The wait for the next change, or `None` once the lever was dropped
+    next: Option<NextChange>,
+}
+
+impl Edges {
+    fn new(gate: &Gate, target: Gateway) -> Self {
+        let mut gate = gate.clone();
+        let (gateway, epoch) = {
+            let state = gate.receiver.borrow_and_update();
+            (state.gateway, state.epoch)
+        };
+
+        Self {
+            target,
+            cursor: (epoch, gateway),
+            latest: epoch,
+            next: Some(Box::pin(next_change(gate))),
+        }
+    }
+}
+
+impl Stream for Edges {
+    type Item = u64;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            // Every epoch flips the state, so the states in between can be worked out
+            while self.cursor.0 < self.latest {
+                let (epoch, gateway) = self.cursor;
+                self.cursor = (epoch + 1, !gateway);
+
+                if self.cursor.1 == self.target {
+                    return Poll::Ready(Some(self.cursor.0));
+                }
+            }
+
+            let Some(next) = &mut self.next else {
+                return Poll::Ready(None);
+            };
+            let (gate, change) = std::task::ready!(next.as_mut().poll(cx));
+
+            let Some((gateway, epoch)) = change else {
+                self.next = None;
+                continue;
+            };
+            self.next = Some(Box::pin(next_change(gate)));
+
+            if epoch < self.cursor.0 {
+                // The gate was reset, so start over from where it is now
+                self.cursor = (epoch, gateway);
+            }
+            self.latest = epoch;
+        }
+    }
+}
+
+/// Watch all the given `gates` at once, yielding the index of a gate along with its edge whenever one of them changes.
+/// The stream ends once every gate's lever has been dropped.
+///
+/// Like with any single gate, a gate that is raised and lowered again
+/// before the stream gets to look at it doesn't yield anything.
+pub fn merge(gates: impl IntoIterator<Item = Gate>) -> Merge {
+    Merge {
+        watching: gates.into_iter().map(|gate| gate.changes()).collect(),
+        start: 0,
+    }
+}
+
+/// The stream returned by [`merge`]
+pub struct Merge {
+    watching: Vec<Changes>,
+    /// Where to start polling next time, so that one busy gate can't starve the others
+    start: usize,
+}
+
+impl Merge {
+    fn last_seen(&self) -> Vec<Gateway> {
+        self.watching
+            .iter()
+            .map(|changes| changes.last_seen)
+            .collect()
+    }
+}
+
+impl Stream for Merge {
+    type Item = (usize, Edge);
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        let count = this.watching.len();
+
+        for offset in 0..count {
+            let index = (this.start + offset) % count;
+
+            if let Poll::Ready(Some(gateway)) = Pin::new(&mut this.watching[index]).poll_next(cx) {
+                this.start = (index + 1) % count;
+
+                return Poll::Ready(Some((index, Edge::from(gateway))));
+            }
+        }
+
+        if this.watching.iter().all(|changes| changes.next.is_none()) {
+            Poll::Ready(None)
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+/// Watch all the given `gates` at once, yielding all of their states together:
+/// first right away, then again whenever any of them changes.
+/// The stream ends once every gate's lever has been dropped.
+///
+/// The states are in the same order as the `gates` were given in.
+pub fn zip(gates: impl IntoIterator<Item = Gate>) -> Zip {
+    Zip {
+        merge: merge(gates),
+        yielded_initial: false,
+    }
+}
+
+/// The stream returned by [`zip`]
+pub struct Zip {
+    merge: Merge,
+    yielded_initial: bool,
+}
+
+impl Stream for Zip {
+    type Item = Vec<Gateway>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if !self.yielded_initial {
+            self.yielded_initial = true;
+            return Poll::Ready(Some(self.merge.last_seen()));
+        }
+
+        Pin::new(&mut self.merge)
+            .poll_next(cx)
+            .map(|edge| edge.map(|_| self.merge.last_seen()))
+    }
+}
+
+/// Create a gate that is raised while enough of the given `gates` are raised,
+/// as decided by `raised_when` from how many are raised out of how many there are.
+/// One task keeps it up to date, stopping (and dropping the lever) once every source gate's lever was dropped
+/// or every derived gate was dropped.
+#[cfg(feature = "rt")]
+fn derive_from_raised_count(
+    gates: impl IntoIterator<Item = Gate>,
+    raised_when: impl Fn(usize, usize) -> bool + Send + 'static,
+) -> Gate {
+    let gates: Vec<Gate> = gates.into_iter().collect();
+    let total = gates.len();
+    let raised = gates.iter().filter(|gate| gate.is_raised()).count();
+    let initial = if raised_when(raised, total) {
+        Raised
+    } else {
+        Lowered
+    };
+
+    let mut zipped = zip(gates);
+
+    crate::derived::derive(initial, move |lever| async move {
+        while let Some(states) = poll_fn(|cx| Pin::new(&mut zipped).poll_next(cx)).await {
+            let raised = states.iter().filter(|gateway| **gateway == Raised).count();
+
+            lever.set_gateway(if raised_when(raised, total) {
+                Raised
+            } else {
+                Lowered
+            });
+        }
+    })
+}
+
+/// Returns a gate that is raised while all of the given `gates` are raised
+/// (so it's raised if there are no `gates`), like for waiting until every subsystem is ready.
+///
+/// One task keeps it up to date,
+/// stopping (and dropping the lever) once every source gate's lever was dropped or every gate it returned was dropped.
+/// A source gate whose lever was dropped counts as staying in the state it was left in.
+///
+/// This is only available with the `rt` feature.
+/// # Panics
+/// Panics if called outside of a Tokio runtime.
+#[cfg(feature = "rt")]
+#[must_use]
+pub fn all(gates: impl IntoIterator<Item = Gate>) -> Gate {
+    derive_from_raised_count(gates, |raised, total| raised == total)
+}
+
+/// Returns a gate that is raised while any of the given `gates` is raised
+/// (so it's lowered if there are no `gates`), like for "any alarm is going off".
+///
+/// One task keeps it up to date,
+/// stopping (and dropping the lever) once every source gate's lever was dropped or every gate it returned was dropped.
+/// A source gate whose lever was dropped counts as staying in the state it was left in.
+///
+/// This is only available with the `rt` feature.
+/// # Panics
+/// Panics if called outside of a Tokio runtime.
+#[cfg(feature = "rt")]
+#[must_use]
+pub fn any(gates: impl IntoIterator<Item = Gate>) -> Gate {
+    derive_from_raised_count(gates, |raised, _| raised > 0)
+}
+
+/// Returns a gate that is raised while at least `quorum` of the given `gates` are raised,
+/// like for opening up traffic once a majority of replicas are ready.
+///
+/// One task keeps it up to date,
+/// stopping (and dropping the lever) once every source gate's lever was dropped or every gate it returned was dropped.
+/// A source gate whose lever was dropped counts as staying in the state it was left in.
+///
+/// This is only available with the `rt` feature.
+/// # Panics
+/// Panics if `quorum` is more than the number of `gates` (so the gate could never be raised)
+/// or if called outside of a Tokio runtime.
+#[cfg(feature = "rt")]
+#[must_use]
+pub fn quorum(quorum: usize, gates: impl IntoIterator<Item = Gate>) -> Gate {
+    let gates: Vec<Gate> = gates.into_iter().collect();
+    assert!(
+        quorum <= gates.len(),
+        "a quorum can't need more gates than there are"
+    );
+
+    derive_from_raised_count(gates, move |raised, _| raised >= quorum)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{new_lowered, new_raised, Lowered, Raised};
+
+    /// Tests that `race_raised` reports the gate that was raised, and pends until then.
+    #[test]
+    fn race_raised_reports_index() {
+        let (_first_lever, first_gate) = new_lowered();
+        let (second_lever, second_gate) = new_lowered();
+        let mut gates = [first_gate, second_gate];
+
+        let mut racing = tokio_test::task::spawn(race_raised(&mut gates));
+        tokio_test::assert_pending!(racing.poll());
+
+        second_lever.raise().unwrap();
+
+        let (index, result) = tokio_test::assert_ready!(racing.poll());
+        assert_eq!(index, 1);
+        assert!(result.is_ok());
+    }
+
+    /// Tests that `race_lowered` reports a lever dropped while raised as a failure of that gate.
+    #[test]
+    fn race_lowered_reports_lever_drop() {
+        let (first_lever, first_gate) = new_raised();
+        let (_second_lever, second_gate) = new_raised();
+        let mut gates = [first_gate, second_gate];
+
+        drop(first_lever);
+
+        let (index, result) =
+            tokio_test::assert_ready!(tokio_test::task::spawn(race_lowered(&mut gates)).poll());
+        assert_eq!(index, 0);
+        assert!(result.is_err());
+    }
+
+    /// Tests that `all_raised` waits again for a gate that was lowered while waiting for the others.
+    #[test]
+    fn all_raised_rechecks() {
+        let (first_lever, first_gate) = new_raised();
+        let (second_lever, second_gate) = new_lowered();
+        let mut gates = [first_gate, second_gate];
+
+        let mut waiting = tokio_test::task::spawn(all_raised(&mut gates));
+        tokio_test::assert_pending!(waiting.poll());
+
+        first_lever.lower().unwrap();
+        second_lever.raise().unwrap();
+        tokio_test::assert_pending!(waiting.poll());
+
+        first_lever.raise().unwrap();
+        tokio_test::assert_ready_ok!(waiting.poll());
+    }
+
+    /// Tests that `all_lowered` reports every gate whose lever was dropped while raised.
+    #[test]
+    fn all_lowered_reports_dropped_levers() {
+        let (first_lever, first_gate) = new_raised();
+        let (_second_lever, second_gate) = new_lowered();
+        let (third_lever, third_gate) = new_raised();
+        let mut gates = [first_gate, second_gate, third_gate];
+
+        drop(first_lever);
+        drop(third_lever);
+
+        let error =
+            tokio_test::assert_ready_err!(tokio_test::task::spawn(all_lowered(&mut gates)).poll());
+        assert_eq!(error.indices, [0, 2]);
+    }
+
+    /// Tests that `merge` yields the edges of every gate and ends once all levers are dropped.
+    #[test]
+    fn merge_yields_edges_until_levers_dropped() {
+        let (first_lever, first_gate) = new_lowered();
+        let (second_lever, second_gate) = new_raised();
+
+        let mut merged = tokio_test::task::spawn(merge([first_gate, second_gate]));
+
+        tokio_test::assert_pending!(merged.poll_next());
+
+        first_lever.raise().unwrap();
+        assert_eq!(
+            tokio_test::assert_ready!(merged.poll_next()),
+            Some((0, Edge::Rising))
+        );
+
+        second_lever.lower().unwrap();
+        assert_eq!(
+            tokio_test::assert_ready!(merged.poll_next()),
+            Some((1, Edge::Falling))
+        );
+
+        first_lever.lower().unwrap();
+        first_lever.raise().unwrap();
+        tokio_test::assert_pending!(merged.poll_next());
+
+        drop(first_lever);
+        tokio_test::assert_pending!(merged.poll_next());
+        drop(second_lever);
+        assert_eq!(tokio_test::assert_ready!(merged.poll_next()), None);
+    }
+
+    /// Tests that `zip` yields the initial states and then every combined state after a change.
+    #[test]
+    fn zip_yields_combined_states() {
+        let (first_lever, first_gate) = new_lowered();
+        let (second_lever, second_gate) = new_raised();
+
+        let mut zipped = tokio_test::task::spawn(zip([first_gate, second_gate]));
+
+        assert_eq!(
+            tokio_test::assert_ready!(zipped.poll_next()),
+            Some(vec![Lowered, Raised])
+        );
+        tokio_test::assert_pending!(zipped.poll_next());
+
+        first_lever.raise().unwrap();
+        assert_eq!(
+            tokio_test::assert_ready!(zipped.poll_next()),
+            Some(vec![Raised, Raised])
+        );
+
+        drop(first_lever);
+        second_lever.lower().unwrap();
+        assert_eq!(
+            tokio_test::assert_ready!(zipped.poll_next()),
+            Some(vec![Raised, Lowered])
+        );
+
+        drop(second_lever);
+        assert_eq!(tokio_test::assert_ready!(zipped.poll_next()), None);
+    }
+
+    /// Tests that a gate combined with `all` is only raised while every source is.
+    #[cfg(feature = "rt")]
+    #[tokio::test]
+    async fn all_raised_while_every_source_is() {
+        let (first_lever, first_gate) = new_raised();
+        let (second_lever, second_gate) = new_lowered();
+
+        let mut combined = all([first_gate, second_gate]);
+        assert!(combined.is_lowered());
+
+        second_lever.raise().unwrap();
+        combined.raised().await.unwrap();
+
+        first_lever.lower().unwrap();
+        combined.lowered().await.unwrap();
+
+        drop(first_lever);
+        drop(second_lever);
+        assert!(combined.raised().await.is_err());
+    }
+
+    /// Tests that a gate combined with `any` is raised while some source is,
+    /// and its lever is only dropped once every source's lever is.
+    #[cfg(feature = "rt")]
+    #[tokio::test]
+    async fn any_raised_while_some_source_is() {
+        let (first_lever, first_gate) = new_lowered();
+        let (second_lever, second_gate) = new_lowered();
+
+        let mut combined = any([first_gate, second_gate]);
+        assert!(combined.is_lowered());
+
+        first_lever.raise().unwrap();
+        combined.raised().await.unwrap();
+
+        second_lever.raise().unwrap();
+        first_lever.lower().unwrap();
+        drop(first_lever);
+        tokio::task::yield_now().await;
+        assert!(combined.is_raised());
+        assert!(!combined.lever_was_dropped());
+
+        second_lever.lower().unwrap();
+        combined.lowered().await.unwrap();
+
+        drop(second_lever);
+        assert!(combined.raised().await.is_err());
+    }
+
+    /// Tests that a quorum gate is raised while enough sources are.
+    #[cfg(feature = "rt")]
+    #[tokio::test]
+    async fn quorum_raised_while_enough_sources_are() {
+        let (levers, gates): (Vec<_>, Vec<_>) = (0..3).map(|_| new_lowered()).unzip();
+
+        let mut combined = quorum(2, gates);
+        assert!(combined.is_lowered());
+
+        levers[0].raise().unwrap();
+        tokio::task::yield_now().await;
+        assert!(combined.is_lowered());
+
+        levers[2].raise().unwrap();
+        combined.raised().await.unwrap();
+
+        levers[0].lower().unwrap();
+        combined.lowered().await.unwrap();
+    }
+
+    /// Tests that a quorum can't need more gates than there are.
+    #[cfg(feature = "rt")]
+    #[tokio::test]
+    #[should_panic = "more gates than there are"]
+    async fn quorum_checks_size() {
+        let (_lever, gate) = new_raised();
+        let _ = quorum(2, [gate]);
+    }
+
+    /// Tests that a gate's changes are yielded until its lever is dropped.
+    #[test]
+    fn changes_until_lever_dropped() {
+        let (lever, gate) = new_lowered();
+        let mut changes = tokio_test::task::spawn(gate.changes());
+        tokio_test::assert_pending!(changes.poll_next());
+
+        lever.raise().unwrap();
+        assert_eq!(tokio_test::assert_ready!(changes.poll_next()), Some(Raised));
+
+        lever.lower().unwrap();
+        drop(lever);
+        assert_eq!(
+            tokio_test::assert_ready!(changes.poll_next()),
+            Some(Lowered)
+        );
+        assert_eq!(tokio_test::assert_ready!(changes.poll_next()), None);
+    }
+
+    /// Tests that transitions undone before the stream looked are counted as missed.
+    #[test]
+    fn changes_count_missed_transitions() {
+        let (lever, gate) = new_lowered();
+        let mut changes = tokio_test::task::spawn(gate.changes());
+
+        lever.raise().unwrap();
+        lever.lower().unwrap();
+        lever.raise().unwrap();
+        assert_eq!(tokio_test::assert_ready!(changes.poll_next()), Some(Raised));
+        assert_eq!(changes.missed_transitions(), 2);
+
+        lever.lower().unwrap();
+        lever.raise().unwrap();
+        tokio_test::assert_pending!(changes.poll_next());
+        assert_eq!(changes.missed_transitions(), 4);
+    }
+
+    /// Tests that every raise is yielded, even ones undone before the stream looked.
+    #[test]
+    fn each_raise_counts_undone_raises() {
+        let (lever, gate) = new_lowered();
+        let mut raises = tokio_test::task::spawn(gate.each_raise());
+        tokio_test::assert_pending!(raises.poll_next());
+
+        lever.raise().unwrap();
+        lever.lower().unwrap();
+        lever.raise().unwrap();
+        lever.lower().unwrap();
+        assert_eq!(tokio_test::assert_ready!(raises.poll_next()), Some(1));
+        assert_eq!(tokio_test::assert_ready!(raises.poll_next()), Some(3));
+        tokio_test::assert_pending!(raises.poll_next());
+
+        lever.raise().unwrap();
+        drop(lever);
+        assert_eq!(tokio_test::assert_ready!(raises.poll_next()), Some(5));
+        assert_eq!(tokio_test::assert_ready!(raises.poll_next()), None);
+    }
+}
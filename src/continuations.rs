@@ -0,0 +1,219 @@
+use std::future::Future;
+
+use tokio::task::JoinHandle;
+
+use crate::Gate;
+
+/// A handle to a continuation registered with [`Gate::raised_then`] or [`Gate::lowered_then`]
+///
+/// Dropping the handle doesn't cancel the continuation; use [`cancel`](ContinuationHandle::cancel) for that.
+pub struct ContinuationHandle {
+    task: JoinHandle<()>,
+}
+
+impl ContinuationHandle {
+    /// Cancel the continuation: if it hasn't started yet, it never will,
+    /// and if it's running, it's stopped at its next `.await`.
+    pub fn cancel(&self) {
+        self.task.abort();
+    }
+
+    /// Returns `true` if the continuation has finished running
+    /// (or never will, because it was cancelled or the lever was dropped first).
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+}
+
+/// A handle to a cleanup registered with [`Gate::on_next_lowered`]
+///
+/// Dropping the handle cancels the cleanup if it hasn't started yet.
+#[must_use = "the cleanup is cancelled when its handle is dropped"]
+pub struct CleanupHandle {
+    task: JoinHandle<()>,
+}
+
+impl CleanupHandle {
+    /// Cancel the cleanup (if it hasn't started yet), like dropping the handle.
+    pub fn cancel(self) {}
+
+    /// Returns `true` if the gate was lowered (or the lever was dropped) so the cleanup started.
+    #[must_use]
+    pub fn has_fired(&self) -> bool {
+        self.task.is_finished()
+    }
+}
+
+impl Drop for CleanupHandle {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl Gate {
+    /// Run the async closure `f` in a new task the next time the gate is lowered
+    /// (so not right away if it's lowered now, but after it's raised and lowered again)
+    /// or once the lever is dropped, whichever comes first,
+    /// for tearing something down when it gets gated off.
+    ///
+    /// Dropping the returned handle cancels the cleanup if it hasn't started yet.
+    /// Once it has started, it runs to completion even if the handle is dropped.
+    ///
+    /// This is only available with the `rt` feature.
+    /// # Panics
+    /// Panics if called outside of a Tokio runtime.
+    pub fn on_next_lowered<F, Fut>(&self, f: F) -> CleanupHandle
+    where
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let mut gate = self.clone();
+        let epoch = gate.current_epoch();
+
+        CleanupHandle {
+            task: tokio::spawn(async move {
+                // Whether the gate was lowered or the lever was dropped, it's time to clean up
+                let _ = gate.lowered_in_epoch_after(epoch).await;
+                tokio::spawn(f());
+            }),
+        }
+    }
+
+    /// Run the async closure `f` in a new task the next time the gate is raised
+    /// (so not right away if it's raised now, but after it's lowered and raised again).
+    /// If the lever is dropped before that, `f` is never run.
+    ///
+    /// This is only available with the `rt` feature.
+    /// # Panics
+    /// Panics if called outside of a Tokio runtime.
+    pub fn raised_then<F, Fut>(&self, f: F) -> ContinuationHandle
+    where
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let mut gate = self.clone();
+        let epoch = gate.current_epoch();
+
+        ContinuationHandle {
+            task: tokio::spawn(async move {
+                if gate.raised_in_epoch_after(epoch).await.is_ok() {
+                    f().await;
+                }
+            }),
+        }
+    }
+
+    /// Run the async closure `f` in a new task the next time the gate is lowered
+    /// (so not right away if it's lowered now, but after it's raised and lowered again).
+    /// If the lever is dropped before that, `f` is never run.
+    ///
+    /// This is only available with the `rt` feature.
+    /// # Panics
+    /// Panics if called outside of a Tokio runtime.
+    pub fn lowered_then<F, Fut>(&self, f: F) -> ContinuationHandle
+    where
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let mut gate = self.clone();
+        let epoch = gate.current_epoch();
+
+        ContinuationHandle {
+            task: tokio::spawn(async move {
+                if gate.lowered_in_epoch_after(epoch).await.is_ok() {
+                    f().await;
+                }
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::oneshot;
+
+    use crate::{new_lowered, new_raised};
+
+    /// Tests that a continuation runs once the gate is raised, and not before.
+    #[tokio::test]
+    async fn raised_then_runs_once_raised() {
+        let (lever, gate) = new_lowered();
+        let (sender, mut receiver) = oneshot::channel();
+
+        let _handle = gate.raised_then(|| async move {
+            sender.send(()).unwrap();
+        });
+
+        tokio::task::yield_now().await;
+        assert!(receiver.try_recv().is_err());
+
+        lever.raise().unwrap();
+        receiver.await.unwrap();
+    }
+
+    /// Tests that a continuation doesn't run for the state the gate is already in,
+    /// only for the next transition to it.
+    #[tokio::test]
+    async fn lowered_then_waits_for_next_lowering() {
+        let (lever, gate) = new_lowered();
+        let (sender, mut receiver) = oneshot::channel();
+
+        let handle = gate.lowered_then(|| async move {
+            sender.send(()).unwrap();
+        });
+        tokio::task::yield_now().await;
+        assert!(receiver.try_recv().is_err());
+        assert!(!handle.is_finished());
+
+        lever.raise().unwrap();
+        lever.lower().unwrap();
+        receiver.await.unwrap();
+    }
+
+    /// Tests that a cancelled continuation never runs.
+    #[tokio::test]
+    async fn cancelled_continuation_never_runs() {
+        let (lever, gate) = new_raised();
+        let (sender, receiver) = oneshot::channel::<()>();
+
+        let handle = gate.lowered_then(|| async move {
+            sender.send(()).unwrap();
+        });
+        handle.cancel();
+
+        lever.lower().unwrap();
+        assert!(receiver.await.is_err());
+    }
+
+    /// Tests that a cleanup waits for the next lowering rather than the current one.
+    #[tokio::test]
+    async fn on_next_lowered_waits_for_next_lowering() {
+        let (lever, gate) = new_lowered();
+        let (sender, mut receiver) = oneshot::channel();
+
+        let handle = gate.on_next_lowered(|| async move {
+            sender.send(()).unwrap();
+        });
+        tokio::task::yield_now().await;
+        assert!(!handle.has_fired());
+
+        lever.raise().unwrap();
+        lever.lower().unwrap();
+        (&mut receiver).await.unwrap();
+    }
+
+    /// Tests that dropping the handle cancels a cleanup that hasn't started.
+    #[tokio::test]
+    async fn dropping_cleanup_handle_cancels() {
+        let (lever, gate) = new_raised();
+        let (sender, receiver) = oneshot::channel::<()>();
+
+        drop(gate.on_next_lowered(|| async move {
+            sender.send(()).unwrap();
+        }));
+
+        lever.lower().unwrap();
+        receiver.await.unwrap_err();
+    }
+}
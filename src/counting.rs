@@ -0,0 +1,107 @@
+//! Gates that are raised while a count is above zero,
+//! for pausing (or running) something while any one of several reasons holds:
+//! each reason [`hold`](CountingLever::hold)s a guard, and the gate is lowered once the last guard is dropped.
+
+use std::sync::{Arc, Mutex};
+
+use crate::{Gate, Lever, Lowered, Raised};
+
+struct Shared {
+    lever: Lever,
+    /// How many guards are alive, held while raising or lowering so that the gate follows the count in order
+    count: Mutex<usize>,
+}
+
+/// Hands out [`CountGuard`]s, keeping the gate raised while any of them are alive
+#[derive(Clone)]
+pub struct CountingLever {
+    shared: Arc<Shared>,
+}
+
+/// Counts towards keeping the gate raised until it's dropped
+/// (see [`CountingLever::hold`])
+#[must_use = "the count goes back down as soon as the guard is dropped"]
+pub struct CountGuard {
+    shared: Arc<Shared>,
+}
+
+impl Drop for CountGuard {
+    fn drop(&mut self) {
+        let mut count = self.shared.count.lock().unwrap();
+        *count -= 1;
+
+        if *count == 0 {
+            self.shared.lever.set_gateway(Lowered);
+        }
+    }
+}
+
+impl CountingLever {
+    /// Add 1 to the count (raising the gate if it was at 0) until the returned guard is dropped.
+    pub fn hold(&self) -> CountGuard {
+        let mut count = self.shared.count.lock().unwrap();
+        *count += 1;
+
+        if *count == 1 {
+            self.shared.lever.set_gateway(Raised);
+        }
+
+        CountGuard {
+            shared: self.shared.clone(),
+        }
+    }
+
+    /// Returns how many guards are alive.
+    #[must_use]
+    pub fn count(&self) -> usize {
+        *self.shared.count.lock().unwrap()
+    }
+
+    /// Create a new gate observing this lever, like [`Lever::subscribe`].
+    #[must_use]
+    pub fn subscribe(&self) -> Gate {
+        self.shared.lever.subscribe()
+    }
+}
+
+/// Create a gate that is raised while the returned [`CountingLever`] has guards alive
+/// (so it starts out lowered).
+///
+/// Once every clone of the lever and every guard is dropped, the lever counts as dropped.
+#[must_use]
+pub fn new() -> (CountingLever, Gate) {
+    let (lever, gate) = crate::new_lowered();
+
+    let lever = CountingLever {
+        shared: Arc::new(Shared {
+            lever,
+            count: Mutex::new(0),
+        }),
+    };
+
+    (lever, gate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests that the gate is raised while any guard is alive and lowered once they're all dropped.
+    #[test]
+    fn raised_while_counted() {
+        let (lever, gate) = new();
+        assert!(gate.is_lowered());
+
+        let first = lever.hold();
+        let second = lever.hold();
+        assert!(gate.is_raised());
+        assert_eq!(lever.count(), 2);
+
+        drop(first);
+        assert!(gate.is_raised());
+
+        drop(second);
+        assert!(gate.is_lowered());
+        assert_eq!(gate.current_epoch(), 2);
+    }
+}
@@ -0,0 +1,199 @@
+//! Gates over arbitrary shared data, like an async condition variable:
+//! the [`DataLever`] changes the data and waiters [`wait_until`](DataGate::wait_until) it satisfies a condition.
+//!
+//! A plain [`Gate`](crate::Gate) is the special case where the data is a [`Gateway`](crate::Gateway)
+//! and the conditions are "is raised" and "is lowered".
+
+use std::ops::Deref;
+
+use thiserror::Error;
+
+use tokio::sync::watch;
+
+/// Changes the shared data and wakes the [`DataGate`]s waiting on it
+pub struct DataLever<T> {
+    sender: watch::Sender<T>,
+}
+
+/// Waits for the shared data to satisfy a condition
+pub struct DataGate<T> {
+    receiver: watch::Receiver<T>,
+}
+
+impl<T> Clone for DataGate<T> {
+    fn clone(&self) -> Self {
+        Self {
+            receiver: self.receiver.clone(),
+        }
+    }
+}
+
+/// Borrowed access to the shared data
+///
+/// The lever can't change the data while this is held, so don't hold it for long
+/// (and never across an `.await`).
+pub struct DataRef<'a, T>(watch::Ref<'a, T>);
+
+impl<T> Deref for DataRef<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+/// The lever was dropped before the data satisfied the condition, so it never will
+#[derive(Debug, Error)]
+#[error("lever was dropped before the data satisfied the condition")]
+pub struct DataLeverDropped;
+
+/// All the gates were dropped, so changing the data achieves nothing
+#[derive(Debug, Error)]
+#[error("all gates were dropped")]
+pub struct DataGatesDropped;
+
+impl<T> DataLever<T> {
+    /// Replace the data, waking waiters.
+    /// # Errors
+    /// If every gate was dropped, an `Err` is returned (but the data is still replaced).
+    pub fn set(&self, value: T) -> Result<(), DataGatesDropped> {
+        self.update(|data| *data = value)
+    }
+
+    /// Change the data in place, waking waiters.
+    /// # Errors
+    /// If every gate was dropped, an `Err` is returned (but the data is still changed).
+    pub fn update(&self, change: impl FnOnce(&mut T)) -> Result<(), DataGatesDropped> {
+        self.update_if(|data| {
+            change(data);
+            true
+        })
+    }
+
+    /// Change the data in place, only waking waiters if `change` returns `true` (meaning it made a change worth waking them for).
+    /// # Errors
+    /// If every gate was dropped, an `Err` is returned (but the data is still changed).
+    pub fn update_if(&self, change: impl FnOnce(&mut T) -> bool) -> Result<(), DataGatesDropped> {
+        self.sender.send_if_modified(change);
+
+        if self.sender.is_closed() {
+            Err(DataGatesDropped)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Run `transaction` on the data, waking waiters once afterwards,
+    /// so they only ever see the data as it was before or after all of its changes.
+    /// Returns what `transaction` returns.
+    ///
+    /// Nothing else can read or change the data while `transaction` runs, so it should be quick.
+    pub fn transaction<R>(&self, transaction: impl FnOnce(&mut T) -> R) -> R {
+        let mut output = None;
+        self.sender
+            .send_modify(|data| output = Some(transaction(data)));
+
+        output.expect("the transaction was run")
+    }
+
+    /// Borrow the data.
+    #[must_use]
+    pub fn borrow(&self) -> DataRef<'_, T> {
+        DataRef(self.sender.borrow())
+    }
+}
+
+impl<T> DataGate<T> {
+    /// Borrow the data.
+    #[must_use]
+    pub fn borrow(&self) -> DataRef<'_, T> {
+        DataRef(self.receiver.borrow())
+    }
+
+    /// Wait until the data satisfies the `condition`, and borrow it.
+    ///
+    /// The `condition` is checked right away and then after each change,
+    /// so if it's already satisfied, this returns immediately.
+    /// # Errors
+    /// If the lever is dropped while the condition isn't satisfied, an `Err` is returned.
+    /// # Cancel safety
+    /// This method is cancel safe.
+    pub async fn wait_until(
+        &mut self,
+        condition: impl FnMut(&T) -> bool,
+    ) -> Result<DataRef<'_, T>, DataLeverDropped> {
+        self.receiver
+            .wait_for(condition)
+            .await
+            .map(DataRef)
+            .map_err(|_| DataLeverDropped)
+    }
+
+    /// Returns whether the lever has been dropped (so the data will never change again).
+    #[must_use]
+    pub fn lever_was_dropped(&self) -> bool {
+        self.receiver.has_changed().is_err()
+    }
+}
+
+/// Create a lever and gate sharing the `initial` data.
+#[must_use]
+pub fn new<T>(initial: T) -> (DataLever<T>, DataGate<T>) {
+    let (sender, receiver) = watch::channel(initial);
+
+    (DataLever { sender }, DataGate { receiver })
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio_test::{assert_pending, assert_ready, task};
+
+    use super::*;
+
+    /// Tests that waiting only finishes once the data satisfies the condition.
+    #[test]
+    fn waits_until_condition() {
+        let (lever, mut gate) = new(0);
+
+        let mut wait =
+            task::spawn(async move { *gate.wait_until(|count| *count >= 3).await.unwrap() });
+        assert_pending!(wait.poll());
+
+        lever.update(|count| *count += 2).unwrap();
+        assert_pending!(wait.poll());
+
+        lever.update(|count| *count += 2).unwrap();
+        assert!(wait.is_woken());
+        assert_eq!(assert_ready!(wait.poll()), 4);
+    }
+
+    /// Tests that dropping the lever fails waiters whose condition isn't satisfied.
+    #[test]
+    fn lever_dropped() {
+        let (lever, mut gate) = new(false);
+
+        let mut wait = task::spawn(async move { gate.wait_until(|ready| *ready).await.is_err() });
+        assert_pending!(wait.poll());
+
+        drop(lever);
+        assert!(assert_ready!(wait.poll()));
+    }
+
+    /// Tests that waiters only see the data after a whole transaction.
+    #[test]
+    fn transaction_hides_intermediate_states() {
+        let (lever, mut gate) = new((0, 0));
+
+        let mut wait = task::spawn(async move { *gate.wait_until(|(a, b)| a != b).await.unwrap() });
+        assert_pending!(wait.poll());
+
+        let moved = lever.transaction(|(a, b)| {
+            *a += 1;
+            *b += 1;
+            *a
+        });
+        assert_eq!(moved, 1);
+        assert!(wait.is_woken());
+        assert_pending!(wait.poll());
+    }
+}
@@ -0,0 +1,199 @@
+//! Gates whose state is derived from something else by a task driving their lever
+
+use std::future::Future;
+
+use crate::{Gate, Gateway, Lever, Lowered, Raised, State};
+
+/// Create a gate starting in the `initial` state whose lever is driven by the future `drive` returns,
+/// running in a new task.
+///
+/// The task stops once `drive` finishes (dropping the lever)
+/// or once every gate it's driving was dropped (since nobody is looking anymore).
+/// # Panics
+/// Panics if called outside of a Tokio runtime.
+pub(crate) fn derive<F, Fut>(initial: Gateway, drive: F) -> Gate
+where
+    F: FnOnce(Lever) -> Fut,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let (lever, gate) = crate::new(initial);
+    let sender = lever.sender.clone();
+    let driving = drive(lever);
+
+    tokio::spawn(async move {
+        tokio::select! {
+            () = driving => {}
+            () = sender.closed() => {}
+        }
+    });
+
+    gate
+}
+
+impl Gate {
+    /// Returns a gate that is lowered until the given `future` finishes and raised from then on,
+    /// for waiting until something (like initialization) is done.
+    /// The `future` runs in a new task, which is cancelled if every gate is dropped before it finishes.
+    ///
+    /// Once the gate is raised, its lever is dropped (since it will never be lowered again).
+    ///
+    /// This is only available with the `rt` feature.
+    /// # Panics
+    /// Panics if called outside of a Tokio runtime.
+    pub fn from_future<F>(future: F) -> Gate
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        derive(Lowered, |lever| async move {
+            future.await;
+            lever.set_gateway(Raised);
+        })
+    }
+
+    /// Returns a gate that is lowered until the given `future` succeeds and raised from then on,
+    /// like [`Gate::from_future`].
+    /// If the `future` fails instead, the lever is dropped while the gate is lowered,
+    /// so waiting for the gate to be raised returns an `Err(LeverDroppedWhileLowered)`
+    /// instead of waiting forever.
+    ///
+    /// This is only available with the `rt` feature.
+    /// # Panics
+    /// Panics if called outside of a Tokio runtime.
+    pub fn from_try_future<F, E>(future: F) -> Gate
+    where
+        F: Future<Output = Result<(), E>> + Send + 'static,
+    {
+        derive(Lowered, |lever| async move {
+            if future.await.is_ok() {
+                lever.set_gateway(Raised);
+            }
+        })
+    }
+
+    /// Returns a gate that is raised while the value in the given watch channel satisfies the `predicate`,
+    /// so an existing watch channel (like one of configuration) can be waited on as a gate.
+    /// The `predicate` is checked against the current value right away and then after every change.
+    ///
+    /// One task keeps the gate up to date,
+    /// stopping (and dropping the lever) once the `receiver`'s sender is dropped or every gate is dropped.
+    ///
+    /// This is only available with the `rt` feature.
+    /// # Panics
+    /// Panics if called outside of a Tokio runtime.
+    pub fn from_watch<T, P>(mut receiver: tokio::sync::watch::Receiver<T>, mut predicate: P) -> Gate
+    where
+        T: Send + Sync + 'static,
+        P: FnMut(&T) -> bool + Send + 'static,
+    {
+        let mut gateway = move |value: &T| if predicate(value) { Raised } else { Lowered };
+        let initial = gateway(&receiver.borrow_and_update());
+
+        derive(initial, move |lever| async move {
+            while receiver.changed().await.is_ok() {
+                let next = gateway(&receiver.borrow_and_update());
+                lever.set_gateway(next);
+            }
+        })
+    }
+
+    /// Returns a gate that is raised while the boolean in the given watch channel is `raised_when`
+    /// (so pass `true` if the boolean means "open", and `false` if it means "paused"),
+    /// like [`Gate::from_watch`] without having to write out the predicate.
+    ///
+    /// This is only available with the `rt` feature.
+    /// # Panics
+    /// Panics if called outside of a Tokio runtime.
+    pub fn from_bool_receiver(
+        receiver: tokio::sync::watch::Receiver<bool>,
+        raised_when: bool,
+    ) -> Gate {
+        Gate::from_watch(receiver, move |value| *value == raised_when)
+    }
+
+    /// Returns a new gate following this one, but lowered while this one is raised and raised while it's lowered,
+    /// so code that takes "raised" to mean "stop" can share a gate with code that takes it to mean "go".
+    ///
+    /// One task keeps the new gate up to date,
+    /// stopping (and dropping the lever) once this gate's lever is dropped or every new gate is dropped.
+    ///
+    /// This is only available with the `rt` feature.
+    /// # Panics
+    /// Panics if called outside of a Tokio runtime.
+    #[must_use]
+    pub fn inverted(&self) -> Gate {
+        Gate::from_watch(self.receiver.clone(), |state: &State| {
+            state.gateway == Lowered
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::oneshot;
+
+    use super::*;
+
+    /// Tests that a gate from a future is raised once the future finishes.
+    #[tokio::test]
+    async fn raised_once_future_finishes() {
+        let (finish, finished) = oneshot::channel::<()>();
+        let mut gate = Gate::from_future(async move {
+            let _ = finished.await;
+        });
+        assert!(gate.is_lowered());
+
+        finish.send(()).unwrap();
+        gate.raised().await.unwrap();
+    }
+
+    /// Tests that a gate from a failing future reports that it will never be raised.
+    #[tokio::test]
+    async fn failure_drops_lever_while_lowered() {
+        let mut gate = Gate::from_try_future(async { Err::<(), _>("failed") });
+
+        gate.raised().await.unwrap_err();
+        assert!(gate.is_lowered());
+    }
+
+    /// Tests that a gate from a watch channel follows whether its value satisfies the predicate.
+    #[tokio::test]
+    async fn follows_watch_predicate() {
+        let (sender, receiver) = tokio::sync::watch::channel(3);
+        let mut gate = Gate::from_watch(receiver, |connections| *connections > 0);
+        assert!(gate.is_raised());
+
+        sender.send(0).unwrap();
+        gate.lowered().await.unwrap();
+
+        sender.send(1).unwrap();
+        gate.raised().await.unwrap();
+
+        drop(sender);
+        gate.lowered().await.unwrap_err();
+    }
+
+    /// Tests that a gate from a boolean watch channel is raised while it has the given value.
+    #[tokio::test]
+    async fn follows_bool_receiver() {
+        let (sender, receiver) = tokio::sync::watch::channel(true);
+        let mut gate = Gate::from_bool_receiver(receiver, false);
+        assert!(gate.is_lowered());
+
+        sender.send(false).unwrap();
+        gate.raised().await.unwrap();
+    }
+
+    /// Tests that an inverted gate is in the opposite state of its source.
+    #[tokio::test]
+    async fn inverted_follows_opposite_state() {
+        let (lever, gate) = crate::new_raised();
+        let mut inverted = gate.inverted();
+        assert!(inverted.is_lowered());
+
+        lever.lower().unwrap();
+        inverted.raised().await.unwrap();
+
+        drop(lever);
+        assert!(inverted.lowered().await.is_err());
+    }
+}
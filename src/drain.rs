@@ -0,0 +1,91 @@
+use std::sync::Arc;
+
+use tokio::sync::watch;
+
+use crate::{Gate, Lever, LeverDroppedWhileLowered, Lowered, Raised};
+
+/// Proof of having entered a raised gate (see [`Gate::enter`]),
+/// which [`Lever::lower_and_drain`] waits to be dropped
+#[derive(Debug)]
+#[must_use = "the permit is released as soon as it's dropped"]
+pub struct Permit {
+    permits: Arc<watch::Sender<usize>>,
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        self.permits.send_modify(|count| *count -= 1);
+    }
+}
+
+impl Gate {
+    /// Wait until the gate is raised, then enter it,
+    /// getting a permit that keeps [`Lever::lower_and_drain`] waiting until it's dropped.
+    /// This is for "stop accepting work, then wait for work in flight" pauses:
+    /// take a permit for each piece of work and drop it when the work is done.
+    ///
+    /// A permit is only handed out while the gate is raised,
+    /// so once the gate is lowered, no new permits are handed out until it's raised again.
+    /// # Errors
+    /// If the lever is dropped while the gate is lowered, an `Err` is returned.
+    /// # Cancel safety
+    /// This method is cancel safe: a permit is only taken right before it's returned.
+    pub async fn enter(&mut self) -> Result<Permit, LeverDroppedWhileLowered> {
+        loop {
+            self.raised().await?;
+
+            // Reading the state holds off the lever, so it can't be lowered between checking and entering
+            let state = self.receiver.borrow();
+            if state.gateway == Raised {
+                state.permits.send_modify(|count| *count += 1);
+
+                return Ok(Permit {
+                    permits: Arc::clone(&state.permits),
+                });
+            }
+        }
+    }
+}
+
+impl Lever {
+    /// Lower the gate, then wait until every [`Permit`] handed out by its gates is dropped,
+    /// so that once this finishes, nothing is in the gate and nothing else can enter it until it's raised again.
+    ///
+    /// This lowers the gate even if every gate was dropped (permits can outlive their gates).
+    /// # Cancel safety
+    /// This method is cancel safe (but the gate stays lowered if it's cancelled).
+    pub async fn lower_and_drain(&self) {
+        self.set_gateway(Lowered);
+
+        let mut permits = self.sender.borrow().permits.subscribe();
+        // The channel can't close while we hold the lever, which holds the state, which holds the sender
+        let _ = permits.wait_for(|count| *count == 0).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio_test::{assert_pending, assert_ready, assert_ready_ok, task};
+
+    use crate::new_raised;
+
+    /// Tests that draining waits for outstanding permits and stops new ones from being handed out.
+    #[test]
+    fn lower_and_drain_waits_for_permits() {
+        let (lever, mut gate) = new_raised();
+
+        let permit = assert_ready_ok!(task::spawn(gate.enter()).poll());
+
+        let mut draining = task::spawn(lever.lower_and_drain());
+        assert_pending!(draining.poll());
+        assert!(gate.is_lowered());
+
+        let mut entering = task::spawn(gate.enter());
+        assert_pending!(entering.poll());
+
+        drop(permit);
+        assert!(draining.is_woken());
+        assert_ready!(draining.poll());
+        assert_pending!(entering.poll());
+    }
+}
@@ -0,0 +1,97 @@
+use std::future::Future;
+
+use crate::{GateDropped, Lever, Lowered};
+
+/// A guard that keeps the gate raised until it's dropped
+/// (see [`Lever::raise_guard`])
+#[must_use = "the gate is lowered again as soon as the guard is dropped"]
+pub struct RaiseGuard {
+    lever: Lever,
+}
+
+impl Drop for RaiseGuard {
+    fn drop(&mut self) {
+        // Not going through `lower` so that a dropped gate doesn't count as misuse
+        // (which would panic while panicking if the guard is dropped by a panic)
+        self.lever.set_gateway(Lowered);
+    }
+}
+
+impl Lever {
+    /// Raise the gate, returning a guard that lowers it again when dropped
+    /// (including when the scope holding it ends early by returning, `?`, or panicking).
+    ///
+    /// Unlike a [`std::sync::Mutex`], the gate isn't poisoned by a panic:
+    /// it's lowered the same as on any other way out of the scope, and can be raised again.
+    /// # Errors
+    /// If the gate was dropped, an `Err` is returned.
+    /// # Panics
+    /// With the `strict` feature enabled in debug builds, this panics instead of returning an `Err`.
+    pub fn raise_guard(&self) -> Result<RaiseGuard, GateDropped> {
+        self.raise()?;
+
+        Ok(RaiseGuard {
+            lever: self.clone(),
+        })
+    }
+
+    /// Raise the gate, run the `future`, then lower the gate again, returning the future's output.
+    /// The gate is lowered even if the returned future is dropped before it finishes (cancelled) or the `future` panics.
+    /// # Errors
+    /// If the gate was dropped, an `Err` is returned (and the `future` isn't run).
+    /// # Panics
+    /// With the `strict` feature enabled in debug builds, this panics instead of returning an `Err`.
+    pub async fn while_raised<F: Future>(&self, future: F) -> Result<F::Output, GateDropped> {
+        let _guard = self.raise_guard()?;
+
+        Ok(future.await)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::new_lowered;
+
+    /// Tests that the gate is raised while the guard is alive and lowered once it's dropped.
+    #[test]
+    fn raised_while_guard_alive() {
+        let (lever, gate) = new_lowered();
+
+        let guard = lever.raise_guard().unwrap();
+        assert!(gate.is_raised());
+
+        drop(guard);
+        assert!(gate.is_lowered());
+    }
+
+    /// Tests that the gate is lowered when a panic drops the guard.
+    #[test]
+    fn lowered_on_panic() {
+        let (lever, gate) = new_lowered();
+
+        std::panic::catch_unwind(|| {
+            let _guard = lever.raise_guard().unwrap();
+            panic!("scope failed");
+        })
+        .unwrap_err();
+
+        assert!(gate.is_lowered());
+    }
+
+    /// Tests that the gate is raised while the future runs and lowered afterwards, even if it's cancelled.
+    #[test]
+    fn while_raised_lowers_after_cancel() {
+        let (lever, gate) = new_lowered();
+
+        let mut running = tokio_test::task::spawn(lever.while_raised(std::future::pending::<()>()));
+        tokio_test::assert_pending!(running.poll());
+        assert!(gate.is_raised());
+
+        drop(running);
+        assert!(gate.is_lowered());
+
+        let output = tokio_test::block_on(lever.while_raised(async { 7 })).unwrap();
+        assert_eq!(output, 7);
+        assert!(gate.is_lowered());
+    }
+}
@@ -0,0 +1,174 @@
+use thiserror::Error;
+use tokio::sync::{mpsc, oneshot};
+
+use crate::{GateDropped, Gateway, Lever, Lowered, Raised};
+
+/// A command sent to the task owning the lever, with where to send the reply
+enum Command {
+    Raise(oneshot::Sender<Result<bool, GateDropped>>),
+    Lower(oneshot::Sender<Result<bool, GateDropped>>),
+    Toggle(oneshot::Sender<Result<Gateway, GateDropped>>),
+    Query(oneshot::Sender<Gateway>),
+}
+
+/// Carry out a change to the gate for a handle, reporting a dropped gate back to it.
+///
+/// This doesn't go through [`Lever::transaction`], since a dropped gate isn't misuse by this task
+/// (and with the `strict` feature, reporting it as misuse would panic the task, stopping every handle).
+fn change<R>(lever: &Lever, change: impl FnOnce(&mut Gateway) -> R) -> Result<R, GateDropped> {
+    if lever.gate_was_dropped() {
+        Err(GateDropped)
+    } else {
+        Ok(lever.change_gateway(change))
+    }
+}
+
+/// A command sent through a [`LeverHandle`] didn't succeed
+#[derive(Debug, Error)]
+pub enum LeverHandleError {
+    /// The gate was dropped, so changing it achieves nothing
+    #[error(transparent)]
+    GateDropped(#[from] GateDropped),
+    /// The task owning the lever stopped (because its runtime shut down), so the command wasn't carried out
+    #[error("the task owning the lever stopped")]
+    Stopped,
+}
+
+/// A handle that sends commands to a task owning a [`Lever`],
+/// for components that shouldn't share the lever directly (like actors that only talk through channels)
+///
+/// Commands are carried out in the order they're sent.
+/// Only a limited number can be waiting at once, so sending waits while the task is behind.
+/// Cloning a handle makes another handle to the same lever,
+/// and the task (and lever) is dropped once every handle is dropped.
+///
+/// This is only available with the `rt` feature.
+#[derive(Clone)]
+pub struct LeverHandle {
+    sender: mpsc::Sender<Command>,
+}
+
+impl LeverHandle {
+    /// Move the `lever` into a new task, which carries out commands sent by the returned handle.
+    /// At most `buffer` commands can be waiting to be carried out at once.
+    /// # Panics
+    /// Panics if `buffer` is 0 or if called outside of a Tokio runtime.
+    #[must_use]
+    pub fn new(lever: Lever, buffer: usize) -> Self {
+        let (sender, mut receiver) = mpsc::channel(buffer);
+
+        tokio::spawn(async move {
+            // Replies to senders that stopped waiting for them are dropped
+            while let Some(command) = receiver.recv().await {
+                match command {
+                    Command::Raise(reply) => {
+                        let _ = reply.send(change(&lever, |gateway| {
+                            std::mem::replace(gateway, Raised) != Raised
+                        }));
+                    }
+                    Command::Lower(reply) => {
+                        let _ = reply.send(change(&lever, |gateway| {
+                            std::mem::replace(gateway, Lowered) != Lowered
+                        }));
+                    }
+                    Command::Toggle(reply) => {
+                        let _ = reply.send(change(&lever, |gateway| {
+                            *gateway = !*gateway;
+                            *gateway
+                        }));
+                    }
+                    Command::Query(reply) => {
+                        let _ = reply.send(lever.sender.borrow().gateway);
+                    }
+                }
+            }
+        });
+
+        Self { sender }
+    }
+
+    async fn send<T>(
+        &self,
+        command: impl FnOnce(oneshot::Sender<T>) -> Command,
+    ) -> Result<T, LeverHandleError> {
+        let (reply, replied) = oneshot::channel();
+
+        self.sender
+            .send(command(reply))
+            .await
+            .map_err(|_| LeverHandleError::Stopped)?;
+
+        replied.await.map_err(|_| LeverHandleError::Stopped)
+    }
+
+    /// Raise the gate (see [`Lever::raise`]), returning whether it was lowered before.
+    /// # Errors
+    /// If the gate was dropped or the task owning the lever stopped, an `Err` is returned.
+    pub async fn raise(&self) -> Result<bool, LeverHandleError> {
+        Ok(self.send(Command::Raise).await??)
+    }
+
+    /// Lower the gate (see [`Lever::lower`]), returning whether it was raised before.
+    /// # Errors
+    /// If the gate was dropped or the task owning the lever stopped, an `Err` is returned.
+    pub async fn lower(&self) -> Result<bool, LeverHandleError> {
+        Ok(self.send(Command::Lower).await??)
+    }
+
+    /// Flip the gate's state (see [`Lever::toggle`]), returning the state it's in afterwards.
+    /// # Errors
+    /// If the gate was dropped or the task owning the lever stopped, an `Err` is returned.
+    pub async fn toggle(&self) -> Result<Gateway, LeverHandleError> {
+        Ok(self.send(Command::Toggle).await??)
+    }
+
+    /// Returns the state the gate is in.
+    /// # Errors
+    /// If the task owning the lever stopped, an `Err` is returned.
+    pub async fn query(&self) -> Result<Gateway, LeverHandleError> {
+        self.send(Command::Query).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{new_lowered, Lowered, Raised};
+
+    /// Tests that commands sent through the handle are carried out in order.
+    #[tokio::test]
+    async fn carries_out_commands() {
+        let (lever, gate) = new_lowered();
+        let handle = LeverHandle::new(lever, 1);
+
+        handle.raise().await.unwrap();
+        assert!(gate.is_raised());
+        assert_eq!(handle.toggle().await.unwrap(), Lowered);
+        assert_eq!(handle.query().await.unwrap(), Lowered);
+
+        handle.clone().lower().await.unwrap();
+        assert_eq!(handle.toggle().await.unwrap(), Raised);
+    }
+
+    /// Tests that a dropped gate is reported to the handle (even with the `strict` feature).
+    #[tokio::test]
+    async fn reports_dropped_gate() {
+        let (lever, gate) = new_lowered();
+        let handle = LeverHandle::new(lever, 1);
+
+        drop(gate);
+        assert!(matches!(
+            handle.raise().await,
+            Err(LeverHandleError::GateDropped(_))
+        ));
+    }
+
+    /// Tests that dropping every handle drops the lever.
+    #[tokio::test]
+    async fn dropping_handles_drops_lever() {
+        let (lever, mut gate) = new_lowered();
+        drop(LeverHandle::new(lever, 1));
+
+        gate.raised().await.unwrap_err();
+    }
+}
@@ -1,9 +1,121 @@
-use std::{ops::Not, str::FromStr};
+use std::{
+    future::{Future, IntoFuture},
+    ops::Not,
+    pin::Pin,
+    str::FromStr,
+    sync::{Arc, OnceLock},
+    task::{Context, Poll},
+};
 
 use thiserror::Error;
-use tokio::sync::watch;
+
+use tokio::sync::{watch, Notify};
+
+mod atomic;
+pub use atomic::AtomicGateway;
+
+pub mod traits;
+
+pub mod combinators;
+
+pub mod threshold;
+
+pub mod data;
+
+pub mod typed;
+
+pub mod lockable;
+
+pub mod counting;
+
+mod builder;
+pub use builder::GateBuilder;
+
+mod poll_gate;
+pub use poll_gate::PollGate;
+
+mod blocking;
+
+mod weak;
+pub use weak::{WeakGate, WeakLever};
+
+mod guard;
+pub use guard::RaiseGuard;
+
+mod drain;
+pub use drain::Permit;
+
+mod wait;
+pub use wait::{WaitLowered, WaitRaised};
+
+mod static_gate;
+pub use static_gate::StaticGate;
+
+mod snapshot;
+pub use snapshot::GateSnapshot;
+
+mod abort;
+pub use abort::LoweredBeforeFinished;
+
+#[cfg(feature = "rt")]
+mod derived;
+
+#[cfg(feature = "rt")]
+mod work_queue;
+#[cfg(feature = "rt")]
+pub use work_queue::{GatedWorkQueue, WorkQueueClosed};
+
+#[cfg(feature = "rt")]
+mod continuations;
+#[cfg(feature = "rt")]
+pub use continuations::{CleanupHandle, ContinuationHandle};
+
+#[cfg(feature = "rt")]
+mod lever_handle;
+#[cfg(feature = "rt")]
+pub use lever_handle::{LeverHandle, LeverHandleError};
+
+#[cfg(feature = "time")]
+mod time;
+#[cfg(feature = "time")]
+pub use time::{LoweredTimeoutError, RaisedTimeoutError};
+
+#[cfg(feature = "time")]
+pub mod token_bucket;
+
+#[cfg(feature = "time")]
+pub mod record;
+
+#[cfg(feature = "time")]
+pub mod two_phase;
+
+#[cfg(feature = "persist")]
+pub mod persist;
+
+#[cfg(feature = "switchboard")]
+mod switchboard;
+#[cfg(feature = "switchboard")]
+pub use switchboard::{Switchboard, UnknownGate};
+
+#[cfg(feature = "tower")]
+pub mod tower;
+
+#[cfg(feature = "tokio-util")]
+mod cancellation;
+
+#[cfg(feature = "stop-signal")]
+pub mod stop_signal;
+
+#[cfg(feature = "tracing")]
+mod tracing;
+#[cfg(feature = "tracing")]
+pub use crate::tracing::EnteredWhileRaised;
+
+#[cfg(feature = "test-util")]
+pub mod test_util;
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Gateway {
     Raised,
     Lowered,
@@ -51,6 +163,64 @@ impl FromStr for Gateway {
     }
 }
 
+impl Gateway {
+    /// Returns `true` if this is [`Raised`] and `false` if it's [`Lowered`].
+    #[must_use]
+    #[inline]
+    pub const fn as_bool(self) -> bool {
+        matches!(self, Raised)
+    }
+}
+
+impl From<bool> for Gateway {
+    /// Returns [`Raised`] for `true` and [`Lowered`] for `false`.
+    #[inline]
+    fn from(raised: bool) -> Self {
+        if raised {
+            Raised
+        } else {
+            Lowered
+        }
+    }
+}
+
+impl From<Gateway> for bool {
+    /// Returns `true` for [`Raised`] and `false` for [`Lowered`].
+    #[inline]
+    fn from(gateway: Gateway) -> Self {
+        gateway.as_bool()
+    }
+}
+
+/// A change in a gate's state
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Edge {
+    /// The gate went from lowered to raised
+    Rising,
+    /// The gate went from raised to lowered
+    Falling,
+}
+
+impl From<Gateway> for Edge {
+    /// Returns the edge that ends in the given `gateway`.
+    fn from(gateway: Gateway) -> Self {
+        match gateway {
+            Raised => Edge::Rising,
+            Lowered => Edge::Falling,
+        }
+    }
+}
+
+impl From<Edge> for Gateway {
+    /// Returns the gateway the given `edge` ends in.
+    fn from(edge: Edge) -> Self {
+        match edge {
+            Edge::Rising => Raised,
+            Edge::Falling => Lowered,
+        }
+    }
+}
+
 /// The gate was dropped, but we still know what value it had before dropping
 #[derive(Debug, Error)]
 #[error("gate was {0} before dropping")]
@@ -71,58 +241,281 @@ pub struct LeverDroppedWhileRaised;
 #[error("lever was dropped while lowered")]
 pub struct LeverDroppedWhileLowered;
 
+/// The lever was dropped before raising the gate in the way that was being waited for
+#[derive(Debug, Error)]
+#[error("lever was dropped before raising the gate")]
+pub struct LeverDroppedBeforeRaise;
+
+/// The lever was dropped before lowering the gate in the way that was being waited for
+#[derive(Debug, Error)]
+#[error("lever was dropped before lowering the gate")]
+pub struct LeverDroppedBeforeLower;
+
+/// The lever was dropped before the gate got into a state that was being waited for
+#[derive(Debug, Error)]
+#[error("lever was dropped before the gate met the condition")]
+pub struct LeverDroppedBeforeCondition;
+
+/// Report misuse of the crate that is otherwise only signalled by an `Err`.
+/// With the `strict` feature enabled in debug builds, this panics with `message`
+/// so that wiring bugs surface early instead of being silently ignored.
+#[inline]
+#[track_caller]
+fn misuse(message: &str) {
+    if cfg!(all(feature = "strict", debug_assertions)) {
+        panic!("async-gate misuse: {message}");
+    }
+}
+
+/// Everything a lever shares with its gates through their channel
+#[derive(Debug, Clone)]
+struct State {
+    gateway: Gateway,
+    /// How many times the gate has changed state (see [`Gate::current_epoch`])
+    epoch: u64,
+    /// The state to move the gate to once every lever is dropped (see [`Lever::on_drop`])
+    on_drop: Option<Gateway>,
+    /// How many [`Permit`]s are out
+    permits: Arc<watch::Sender<usize>>,
+    /// Wakes [`WaitRaised`] and [`WaitLowered`] futures whenever the channel wakes its own waiters
+    notify: Arc<Notify>,
+    /// How long [`Gate::timed_raised`] and [`Gate::timed_lowered`] wait before giving up
+    #[cfg(feature = "time")]
+    timed_wait_timeout: Option<std::time::Duration>,
+    /// How often the lever may wake waiters, if it's limited
+    #[cfg(feature = "time")]
+    wake_throttle: Option<time::WakeThrottle>,
+}
+
+impl State {
+    fn new(gateway: Gateway) -> Self {
+        Self {
+            gateway,
+            epoch: 0,
+            on_drop: None,
+            permits: Arc::new(watch::channel(0).0),
+            notify: Arc::new(Notify::new()),
+            #[cfg(feature = "time")]
+            timed_wait_timeout: None,
+            #[cfg(feature = "time")]
+            wake_throttle: None,
+        }
+    }
+
+    /// Move to the given `gateway`, starting a new epoch.
+    /// Returns `false` if it was already in that gateway (so nothing changed).
+    fn set_gateway(&mut self, gateway: Gateway) -> bool {
+        if self.gateway == gateway {
+            false
+        } else {
+            self.gateway = gateway;
+            self.epoch += 1;
+            true
+        }
+    }
+
+    /// Go back to the given `gateway` in epoch 0, forgetting the history (but keeping the settings).
+    fn reset(&mut self, gateway: Gateway) {
+        self.gateway = gateway;
+        self.epoch = 0;
+        #[cfg(feature = "time")]
+        if let Some(throttle) = &mut self.wake_throttle {
+            throttle.reset();
+        }
+    }
+}
+
 /// A lever that can [`raise`] and [`lower`] the gate it's associated with
 ///
+/// Cloning a lever gives another lever for the same gate, so several tasks can control it.
+/// The gate only counts its lever as dropped once every clone has been dropped.
+///
 /// [`raise`]: Lever::raise
 /// [`lower`]: Lever::lower
+#[derive(Clone)]
 pub struct Lever {
-    sender: watch::Sender<Gateway>,
+    sender: Arc<LeverShared>,
+}
+
+/// The sending half of a gate's channel, shared by every clone of a lever
+///
+/// Its sending methods shadow the channel's own, so that [`WaitRaised`] and [`WaitLowered`] futures are woken along with the channel's waiters.
+/// The notify comes after the sender so that it wakes them once the sender is dropped (and the gate can tell).
+struct LeverShared(watch::Sender<State>, WakeOnDrop);
+
+/// Wakes every [`WaitRaised`] and [`WaitLowered`] future when dropped
+struct WakeOnDrop(Arc<Notify>);
+
+impl Drop for WakeOnDrop {
+    fn drop(&mut self) {
+        self.0.notify_waiters();
+    }
+}
+
+impl LeverShared {
+    /// Like [`watch::Sender::send_if_modified`], also waking [`WaitRaised`] and [`WaitLowered`] futures if waiters were woken.
+    fn send_if_modified(&self, modify: impl FnOnce(&mut State) -> bool) -> bool {
+        let modified = self.0.send_if_modified(modify);
+        if modified {
+            self.1 .0.notify_waiters();
+        }
+        modified
+    }
+
+    /// Like [`watch::Sender::send_modify`], also waking [`WaitRaised`] and [`WaitLowered`] futures.
+    fn send_modify(&self, modify: impl FnOnce(&mut State)) {
+        self.0.send_modify(modify);
+        self.1 .0.notify_waiters();
+    }
+}
+
+impl std::ops::Deref for LeverShared {
+    type Target = watch::Sender<State>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl Drop for LeverShared {
+    fn drop(&mut self) {
+        // The last lever is being dropped, so this is the last chance to move the gate
+        let on_drop = self.0.borrow().on_drop;
+
+        if let Some(gateway) = on_drop {
+            self.send_if_modified(|state| state.set_gateway(gateway));
+        }
+    }
 }
 
 impl Lever {
+    /// Move the gate to the given `gateway`, waking waiters if that changed anything.
+    /// Returns whether it changed anything.
+    fn set_gateway(&self, gateway: Gateway) -> bool {
+        self.change_gateway(|current| std::mem::replace(current, gateway) != gateway)
+    }
+
+    /// Let `change` move the gate from its current state as one transition, waking waiters if that changed anything
+    fn change_gateway<R>(&self, change: impl FnOnce(&mut Gateway) -> R) -> R {
+        let mut output = None;
+        let change = |state: &mut State| {
+            let mut gateway = state.gateway;
+            output = Some(change(&mut gateway));
+            state.set_gateway(gateway)
+        };
+
+        #[cfg(feature = "time")]
+        if self.sender.borrow().wake_throttle.is_some() {
+            time::change_gateway_throttled(&self.sender, change);
+            return output.expect("the change was applied");
+        }
+
+        self.sender.send_if_modified(change);
+        output.expect("the change was applied")
+    }
+
     /// Raise the gate.
     /// This wakes all tasks waiting on [`Gate::raised`]
     /// (and later calls to it will resolve immediately until the gate is [`lower`]ed).
+    ///
+    /// Raising a gate that is already raised wakes nobody,
+    /// and a waiting task is woken at most once until it's polled again,
+    /// however many times the gate is raised and lowered in between.
+    ///
+    /// Returns `Ok(true)` if the gate was lowered before (so this raised it)
+    /// and `Ok(false)` if it was already raised.
     /// # Errors
     /// If the gate was dropped, an `Err` is returned.
+    /// # Panics
+    /// With the `strict` feature enabled in debug builds, this panics instead of returning an `Err`.
     ///
     /// [`lower`]: Lever::lower
-    pub fn raise(&self) -> Result<(), GateDropped> {
+    pub fn raise(&self) -> Result<bool, GateDropped> {
         if self.gate_was_dropped() {
+            misuse("tried to raise a gate that was dropped");
             Err(GateDropped)
         } else {
-            self.sender.send_if_modified(|gateway| match gateway {
-                Raised => false,
-                Lowered => {
-                    *gateway = Raised;
-                    true
-                }
-            });
-
-            Ok(())
+            Ok(self.set_gateway(Raised))
         }
     }
 
     /// Lower the gate.
     /// This wakes all tasks waiting on [`Gate::lowered`]
     /// (and later calls to it will resolve immediately until the gate is [`raise`]d).
+    ///
+    /// Lowering a gate that is already lowered wakes nobody,
+    /// and a waiting task is woken at most once until it's polled again,
+    /// however many times the gate is raised and lowered in between.
+    ///
+    /// Returns `Ok(true)` if the gate was raised before (so this lowered it)
+    /// and `Ok(false)` if it was already lowered.
     /// # Errors
     /// If the gate was dropped, an `Err` is returned.
+    /// # Panics
+    /// With the `strict` feature enabled in debug builds, this panics instead of returning an `Err`.
     ///
     /// [`raise`]: Lever::raise
-    pub fn lower(&self) -> Result<(), GateDropped> {
+    pub fn lower(&self) -> Result<bool, GateDropped> {
         if self.gate_was_dropped() {
+            misuse("tried to lower a gate that was dropped");
             Err(GateDropped)
         } else {
-            self.sender.send_if_modified(|gateway| match gateway {
-                Lowered => false,
-                Raised => {
-                    *gateway = Lowered;
-                    true
-                }
-            });
+            Ok(self.set_gateway(Lowered))
+        }
+    }
 
-            Ok(())
+    /// Move the gate to the given `gateway` (raising or lowering it), returning the state it was in before,
+    /// so comparing the two tells whether anything changed.
+    /// # Errors
+    /// If the gate was dropped, an `Err` is returned.
+    /// # Panics
+    /// With the `strict` feature enabled in debug builds, this panics instead of returning an `Err`.
+    pub fn set(&self, gateway: Gateway) -> Result<Gateway, GateDropped> {
+        self.transaction(|current| std::mem::replace(current, gateway))
+    }
+
+    /// Raise the gate if `raised` is `true` and lower it if it's `false`, like [`Lever::set`],
+    /// returning whether it was raised before.
+    /// # Errors
+    /// If the gate was dropped, an `Err` is returned.
+    /// # Panics
+    /// With the `strict` feature enabled in debug builds, this panics instead of returning an `Err`.
+    pub fn set_bool(&self, raised: bool) -> Result<bool, GateDropped> {
+        self.set(raised.into()).map(Gateway::as_bool)
+    }
+
+    /// Raise the gate if it's lowered and lower it if it's raised, as one atomic step,
+    /// returning the state it's in afterwards.
+    /// # Errors
+    /// If the gate was dropped, an `Err` is returned.
+    /// # Panics
+    /// With the `strict` feature enabled in debug builds, this panics instead of returning an `Err`.
+    pub fn toggle(&self) -> Result<Gateway, GateDropped> {
+        self.transaction(|gateway| {
+            *gateway = !*gateway;
+            *gateway
+        })
+    }
+
+    /// Run `transaction` on the gate's state, applying whatever it leaves the state as in one transition:
+    /// waiters only see the state it ends in (and are only woken if that's different from before),
+    /// and the epoch goes up by at most 1.
+    /// Returns what `transaction` returns.
+    ///
+    /// Nothing else can change the gate while `transaction` runs, so it should be quick.
+    /// # Errors
+    /// If the gate was dropped, an `Err` is returned (and `transaction` isn't run).
+    /// # Panics
+    /// With the `strict` feature enabled in debug builds, this panics instead of returning an `Err`.
+    pub fn transaction<R>(
+        &self,
+        transaction: impl FnOnce(&mut Gateway) -> R,
+    ) -> Result<R, GateDropped> {
+        if self.gate_was_dropped() {
+            misuse("tried to change a gate that was dropped");
+            Err(GateDropped)
+        } else {
+            Ok(self.change_gateway(transaction))
         }
     }
 
@@ -131,12 +524,12 @@ impl Lever {
     /// If the gate was dropped and was raised before dropping, an `Err(BeforeGateDropped(Raised))` is returned.
     /// Likewise, if the gate was dropped and was lowered before dropping, an `Err(BeforeGateDropped(Lowered))` is returned.
     pub fn is_raised(&self) -> Result<bool, BeforeGateDropped> {
-        let gateway = self.sender.borrow();
+        let gateway = self.sender.borrow().gateway;
 
         if self.gate_was_dropped() {
-            Err(BeforeGateDropped(*gateway))
+            Err(BeforeGateDropped(gateway))
         } else {
-            let is_raised = matches!(*gateway, Raised);
+            let is_raised = matches!(gateway, Raised);
             Ok(is_raised)
         }
     }
@@ -146,85 +539,424 @@ impl Lever {
     /// If the gate was dropped and was lowered before dropping, an `Err(BeforeGateDropped(Lowered))` is returned.
     /// Likewise, if the gate was dropped and was raised before dropping, an `Err(BeforeGateDropped(Raised))` .
     pub fn is_lowered(&self) -> Result<bool, BeforeGateDropped> {
-        let gateway = self.sender.borrow();
+        let gateway = self.sender.borrow().gateway;
 
         if self.gate_was_dropped() {
-            Err(BeforeGateDropped(*gateway))
+            Err(BeforeGateDropped(gateway))
         } else {
-            let is_lowered = matches!(*gateway, Lowered);
+            let is_lowered = matches!(gateway, Lowered);
             Ok(is_lowered)
         }
     }
 
+    /// Returns how many times the gate has changed state (see [`Gate::current_epoch`]).
+    #[must_use]
+    pub fn current_epoch(&self) -> u64 {
+        self.sender.borrow().epoch
+    }
+
     /// Returns `true` if the gate associated with this lever has been dropped
     /// and `false` if it hasn't.
     #[must_use]
     pub fn gate_was_dropped(&self) -> bool {
         self.sender.is_closed()
     }
+
+    /// Wait until every gate associated with this lever has been dropped
+    /// (so nobody is looking at it anymore).
+    /// # Cancel safety
+    /// This method is cancel safe.
+    pub async fn closed(&self) {
+        self.sender.closed().await;
+    }
+
+    /// Keep this lever's gate mirroring the state of the `source` gate
+    /// (starting with the state it's in now) for as long as the returned future is polled,
+    /// like for bridging a gate owned by one module into a gate owned by another.
+    /// Spawn it (or poll it alongside other work) to keep the gates in sync.
+    ///
+    /// This finishes once the `source` gate's lever is dropped (after mirroring its final state)
+    /// or every gate of this lever is dropped (since nobody is looking anymore).
+    /// Changes undone before the future gets to look at the `source` gate aren't mirrored, like with waiting.
+    /// # Cancel safety
+    /// This method is cancel safe: dropping the future just stops the mirroring.
+    pub async fn follow(&self, mut source: Gate) {
+        let mut closed = std::pin::pin!(self.closed());
+
+        loop {
+            let gateway = source.receiver.borrow_and_update().gateway;
+            self.set_gateway(gateway);
+
+            let mut changed = std::pin::pin!(source.receiver.changed());
+            let finished = std::future::poll_fn(|cx| {
+                if closed.as_mut().poll(cx).is_ready() {
+                    return Poll::Ready(true);
+                }
+
+                changed.as_mut().poll(cx).map(|result| result.is_err())
+            })
+            .await;
+
+            if finished {
+                return;
+            }
+        }
+    }
+
+    /// Move the gate to the given `gateway` once every lever is dropped
+    /// (instead of leaving it in whatever state it was in),
+    /// so that it ends in a known safe state even if its controller goes away unexpectedly.
+    ///
+    /// Waiters for that state are then woken successfully,
+    /// and waiters for the other state fail like they would whenever the lever is dropped.
+    /// This can also be set up front with [`GateBuilder::on_lever_drop`].
+    pub fn on_drop(&self, gateway: Gateway) {
+        self.sender.send_if_modified(|state| {
+            state.on_drop = Some(gateway);
+            false
+        });
+    }
+
+    /// Create another gate controlled by this lever, like cloning one of its gates.
+    ///
+    /// This works even if every gate was dropped, after which [`gate_was_dropped`](Lever::gate_was_dropped)
+    /// returns `false` again (until this gate is dropped too).
+    #[must_use]
+    pub fn subscribe(&self) -> Gate {
+        Gate {
+            receiver: self.sender.subscribe(),
+        }
+    }
 }
 
 /// A gate that can be checked if [`is_raised`] or [`is_lowered`] immediately,
 /// or can be waited on to be [`raised`] or [`lowered`].
 ///
+/// A gate is a handle to state shared with its lever and every other clone of it,
+/// so it is only two pointers wide
+/// and cloning it is just a couple of reference count increments (no allocation).
+///
 /// [`is_raised`]: Gate::is_raised
 /// [`is_lowered`]: Gate::is_lowered
 /// [`raised`]: Gate::raised
 /// [`lowered`]: Gate::lowered
 #[derive(Clone)]
 pub struct Gate {
-    receiver: watch::Receiver<Gateway>,
+    receiver: watch::Receiver<State>,
 }
 
 impl Gate {
+    /// Returns a gate that is raised forever, for wiring up components that require a `Gate`
+    /// when nothing should ever lower it (like in tests).
+    ///
+    /// Its lever is never dropped, so waiting for it to be [`lowered`] never finishes.
+    /// See [`Gate::frozen`] for a gate whose waits fail instead.
+    ///
+    /// [`lowered`]: Gate::lowered
+    #[must_use]
+    pub fn always_raised() -> Self {
+        static ALWAYS_RAISED: OnceLock<(Lever, Gate)> = OnceLock::new();
+
+        ALWAYS_RAISED.get_or_init(new_raised).1.clone()
+    }
+
+    /// Returns a gate that is lowered forever, for wiring up components that require a `Gate`
+    /// when nothing should ever raise it (like in tests).
+    ///
+    /// Its lever is never dropped, so waiting for it to be [`raised`] never finishes.
+    /// See [`Gate::frozen`] for a gate whose waits fail instead.
+    ///
+    /// [`raised`]: Gate::raised
+    #[must_use]
+    pub fn always_lowered() -> Self {
+        static ALWAYS_LOWERED: OnceLock<(Lever, Gate)> = OnceLock::new();
+
+        ALWAYS_LOWERED.get_or_init(new_lowered).1.clone()
+    }
+
+    /// Returns a gate stuck in the given state because its lever was already dropped.
+    ///
+    /// Waiting for the gate to be in the state it's stuck in finishes immediately,
+    /// and waiting for the opposite state fails immediately
+    /// (with [`LeverDroppedWhileRaised`] or [`LeverDroppedWhileLowered`]).
+    #[must_use]
+    pub fn frozen(gateway: Gateway) -> Self {
+        let (_, gate) = new(gateway);
+
+        gate
+    }
+
     /// Returns true if the gate (even if the lever has been dropped) is raised and false if it's lowered.
     #[must_use]
     pub fn is_raised(&self) -> bool {
-        matches!(*self.receiver.borrow(), Raised)
+        matches!(self.receiver.borrow().gateway, Raised)
     }
 
     /// Returns true if the gate (even if the lever has been dropped) is lowered and false if it's raised.
     #[must_use]
     pub fn is_lowered(&self) -> bool {
-        matches!(*self.receiver.borrow(), Lowered)
+        matches!(self.receiver.borrow().gateway, Lowered)
     }
 
     /// Wait until the gate is raised
     /// (by a call to [`Lever::raise`])
     /// # Errors
     /// If the lever is dropped while the gate is lowered, an `Err` is returned.
-    pub async fn raised(&mut self) -> Result<(), LeverDroppedWhileLowered> {
+    /// # Cancel safety
+    /// This method is cancel safe.
+    /// Dropping the future before it completes (like when it loses a `tokio::select!`)
+    /// doesn't lose any raises: the next call checks the gate's current state before waiting again.
+    /// # Allocation
+    /// Waiting doesn't allocate: the waiter is registered inside the returned future itself.
+    ///
+    /// That future is a [`WaitRaised`], which can be named (like to keep it in a struct).
+    pub fn raised(&mut self) -> WaitRaised<'_> {
+        WaitRaised::new(self)
+    }
+
+    /// Wait until the gate is lowered
+    /// (by a call to [`Lever::lower`])
+    /// # Errors
+    /// If the lever is dropped while the gate is raised, an `Err` is returned.
+    /// # Cancel safety
+    /// This method is cancel safe.
+    /// Dropping the future before it completes (like when it loses a `tokio::select!`)
+    /// doesn't lose any lowers: the next call checks the gate's current state before waiting again.
+    /// # Allocation
+    /// Waiting doesn't allocate: the waiter is registered inside the returned future itself.
+    ///
+    /// That future is a [`WaitLowered`], which can be named (like to keep it in a struct).
+    pub fn lowered(&mut self) -> WaitLowered<'_> {
+        WaitLowered::new(self)
+    }
+
+    /// Wait until the gate's state satisfies the given `condition`
+    /// (checking the current state first, and then every state the gate is seen moving to),
+    /// returning the state that satisfied it.
+    ///
+    /// This is for writing waits of your own on top of the gate;
+    /// [`Gate::raised`] is like `wait_for(|gateway| gateway == Raised)`.
+    /// # Errors
+    /// If the lever is dropped before the condition is satisfied, an `Err` is returned.
+    /// # Cancel safety
+    /// This method is cancel safe, for the same reasons as [`Gate::raised`].
+    pub async fn wait_for(
+        &mut self,
+        mut condition: impl FnMut(Gateway) -> bool,
+    ) -> Result<Gateway, LeverDroppedBeforeCondition> {
         match self
             .receiver
-            .wait_for(|gateway| matches!(*gateway, Raised))
+            .wait_for(|state| condition(state.gateway))
             .await
         {
-            Ok(_) => Ok(()),
-            Err(_) => Err(LeverDroppedWhileLowered),
+            Ok(state) => Ok(state.gateway),
+            Err(_) => Err(LeverDroppedBeforeCondition),
         }
     }
 
-    /// Wait until the gate is lowered
-    /// (by a call to [`Lever::lower`])
+    /// Wait until the gate is raised, like [`Gate::raised`],
+    /// but through a shared reference (for gates kept in shared state, like behind an `Arc`).
+    /// Each call waits with its own clone of the gate.
+    /// # Errors
+    /// If the lever is dropped while the gate is lowered, an `Err` is returned.
+    /// # Cancel safety
+    /// This method is cancel safe, for the same reasons as [`Gate::raised`].
+    pub async fn raised_shared(&self) -> Result<(), LeverDroppedWhileLowered> {
+        self.clone().raised().await
+    }
+
+    /// Wait until the gate is lowered, like [`Gate::lowered`],
+    /// but through a shared reference (for gates kept in shared state, like behind an `Arc`).
+    /// Each call waits with its own clone of the gate.
+    /// # Errors
+    /// If the lever is dropped while the gate is raised, an `Err` is returned.
+    /// # Cancel safety
+    /// This method is cancel safe, for the same reasons as [`Gate::lowered`].
+    pub async fn lowered_shared(&self) -> Result<(), LeverDroppedWhileRaised> {
+        self.clone().lowered().await
+    }
+
+    /// Wait until the gate is raised, like [`Gate::raised`],
+    /// but taking the gate so the returned future is `'static`
+    /// (for handing to `tokio::spawn` or keeping in a collection of futures).
+    /// # Errors
+    /// If the lever is dropped while the gate is lowered, an `Err` is returned.
+    /// # Cancel safety
+    /// This method is cancel safe, for the same reasons as [`Gate::raised`].
+    pub async fn raised_owned(mut self) -> Result<(), LeverDroppedWhileLowered> {
+        self.raised().await
+    }
+
+    /// Wait until the gate is lowered, like [`Gate::lowered`],
+    /// but taking the gate so the returned future is `'static`
+    /// (for handing to `tokio::spawn` or keeping in a collection of futures).
     /// # Errors
     /// If the lever is dropped while the gate is raised, an `Err` is returned.
-    pub async fn lowered(&mut self) -> Result<(), LeverDroppedWhileRaised> {
+    /// # Cancel safety
+    /// This method is cancel safe, for the same reasons as [`Gate::lowered`].
+    pub async fn lowered_owned(mut self) -> Result<(), LeverDroppedWhileRaised> {
+        self.lowered().await
+    }
+
+    /// Returns how many times the gate has changed state (its epoch).
+    /// This starts at 0 when the gate is created and goes up by 1 on every raise and lower that changes anything,
+    /// so comparing epochs tells whether the gate changed in between (even if it changed back).
+    #[must_use]
+    pub fn current_epoch(&self) -> u64 {
+        self.receiver.borrow().epoch
+    }
+
+    /// Wait until the gate is raised in an epoch after the given `epoch`
+    /// (so, by a raise that happened after the gate was in that epoch),
+    /// returning the epoch it's raised in.
+    ///
+    /// Passing in the [`current_epoch`] from before starting to observe the gate
+    /// makes sure a raise from before that point doesn't count.
+    /// # Errors
+    /// If the lever is dropped before such a raise, an `Err` is returned.
+    /// # Cancel safety
+    /// This method is cancel safe, for the same reasons as [`Gate::raised`].
+    ///
+    /// [`current_epoch`]: Gate::current_epoch
+    pub async fn raised_in_epoch_after(
+        &mut self,
+        epoch: u64,
+    ) -> Result<u64, LeverDroppedBeforeRaise> {
+        match self
+            .receiver
+            .wait_for(|state| matches!(state.gateway, Raised) && state.epoch > epoch)
+            .await
+        {
+            Ok(state) => Ok(state.epoch),
+            Err(_) => Err(LeverDroppedBeforeRaise),
+        }
+    }
+
+    /// Wait until the gate is lowered in an epoch after the given `epoch`
+    /// (so, by a lower that happened after the gate was in that epoch),
+    /// returning the epoch it's lowered in.
+    ///
+    /// Passing in the [`current_epoch`] from before starting to observe the gate
+    /// makes sure a lower from before that point doesn't count.
+    /// # Errors
+    /// If the lever is dropped before such a lower, an `Err` is returned.
+    /// # Cancel safety
+    /// This method is cancel safe, for the same reasons as [`Gate::lowered`].
+    ///
+    /// [`current_epoch`]: Gate::current_epoch
+    pub async fn lowered_in_epoch_after(
+        &mut self,
+        epoch: u64,
+    ) -> Result<u64, LeverDroppedBeforeLower> {
         match self
             .receiver
-            .wait_for(|gateway| matches!(*gateway, Lowered))
+            .wait_for(|state| matches!(state.gateway, Lowered) && state.epoch > epoch)
             .await
         {
-            Ok(_) => Ok(()),
-            Err(_) => Err(LeverDroppedWhileRaised),
+            Ok(state) => Ok(state.epoch),
+            Err(_) => Err(LeverDroppedBeforeLower),
         }
     }
 
+    /// Wait for the next time the gate is raised, even if it's raised right now
+    /// (so this waits for it to be lowered and then raised again),
+    /// returning the epoch it's raised in.
+    ///
+    /// The gate's epoch is read when this is called rather than when the future is first polled,
+    /// so a transition in between isn't missed.
+    /// # Errors
+    /// If the lever is dropped before the next raise, an `Err` is returned.
+    /// # Cancel safety
+    /// This method is cancel safe, for the same reasons as [`Gate::raised`].
+    pub fn next_raise(
+        &mut self,
+    ) -> impl Future<Output = Result<u64, LeverDroppedBeforeRaise>> + '_ {
+        let epoch = self.current_epoch();
+        self.raised_in_epoch_after(epoch)
+    }
+
+    /// Wait for the next time the gate is lowered, even if it's lowered right now
+    /// (so this waits for it to be raised and then lowered again),
+    /// returning the epoch it's lowered in.
+    ///
+    /// The gate's epoch is read when this is called rather than when the future is first polled,
+    /// so a transition in between isn't missed.
+    /// # Errors
+    /// If the lever is dropped before the next lower, an `Err` is returned.
+    /// # Cancel safety
+    /// This method is cancel safe, for the same reasons as [`Gate::lowered`].
+    pub fn next_lower(
+        &mut self,
+    ) -> impl Future<Output = Result<u64, LeverDroppedBeforeLower>> + '_ {
+        let epoch = self.current_epoch();
+        self.lowered_in_epoch_after(epoch)
+    }
+
     /// Returns `true` if the lever associated with this gate has been dropped
     /// and `false` if it hasn't.
     #[must_use]
     pub fn lever_was_dropped(&self) -> bool {
         self.receiver.has_changed().is_err()
     }
+
+    /// Reset this gate and its `lever` to how they were when created in the `initial` state,
+    /// so that pooled or per-request gates can be reused instead of creating new ones.
+    /// The epoch goes back to 0 and the history of wakeups is forgotten,
+    /// but settings from the [`GateBuilder`] (like the timed wait timeout) are kept.
+    ///
+    /// Waiters on clones of this gate see the reset like any other change:
+    /// ones waiting for the `initial` state are woken,
+    /// while ones waiting for an epoch after some epoch from before the reset
+    /// keep waiting until the gate gets past that epoch again.
+    /// # Panics
+    /// Panics if `lever` isn't this gate's lever.
+    pub fn reset(&mut self, lever: &Lever, initial: Gateway) {
+        assert!(
+            self.receiver.same_channel(&lever.sender.subscribe()),
+            "can only reset a gate with its own lever"
+        );
+
+        lever.sender.send_modify(|state| state.reset(initial));
+        self.receiver.borrow_and_update();
+    }
+}
+
+/// Awaiting `&mut gate` waits until the gate is raised, like [`Gate::raised`].
+impl<'a> IntoFuture for &'a mut Gate {
+    type Output = Result<(), LeverDroppedWhileLowered>;
+    type IntoFuture = WaitRaised<'a>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        self.raised()
+    }
+}
+
+/// A lever is a sink of the states to move its gate to,
+/// so a stream of desired states can be forwarded into it.
+///
+/// Sending never has to wait, and fails once the gate is dropped.
+impl futures_sink::Sink<Gateway> for Lever {
+    type Error = GateDropped;
+
+    fn poll_ready(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), GateDropped>> {
+        if self.gate_was_dropped() {
+            Poll::Ready(Err(GateDropped))
+        } else {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    fn start_send(self: Pin<&mut Self>, gateway: Gateway) -> Result<(), GateDropped> {
+        Lever::set(&self, gateway).map(drop)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), GateDropped>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), GateDropped>> {
+        Poll::Ready(Ok(()))
+    }
 }
 
 /// Create a [`Gate`] in the given `initial` state.
@@ -232,9 +964,19 @@ impl Gate {
 #[must_use]
 #[inline]
 pub fn new(initial: Gateway) -> (Lever, Gate) {
-    let (sender, receiver) = watch::channel(initial);
+    with_state(State::new(initial))
+}
 
-    let lever = Lever { sender };
+fn with_state(mut state: State) -> (Lever, Gate) {
+    // Every channel counts its own permits and wakes its own futures, even if its state was copied (like from a cloned builder)
+    state.permits = Arc::new(watch::channel(0).0);
+    state.notify = Arc::new(Notify::new());
+    let notify = state.notify.clone();
+    let (sender, receiver) = watch::channel(state);
+
+    let lever = Lever {
+        sender: Arc::new(LeverShared(sender, WakeOnDrop(notify))),
+    };
     let gate = Gate { receiver };
 
     (lever, gate)
@@ -256,10 +998,38 @@ pub fn new_lowered() -> (Lever, Gate) {
     new(Lowered)
 }
 
+/// Create a [`Gate`] that is initially raised if `raised` is `true` and lowered if it's `false`.
+/// The [`Lever`] that it is returned with can raise and lower the gate.
+#[must_use]
+#[inline]
+pub fn new_from_bool(raised: bool) -> (Lever, Gate) {
+    new(raised.into())
+}
+
 #[cfg(test)]
 mod tests {
+    use std::{
+        future::Future,
+        pin::pin,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        task::{Context, Wake, Waker},
+    };
+
     use super::*;
 
+    /// Counts how many times it was woken
+    #[derive(Default)]
+    struct CountingWaker(AtomicUsize);
+
+    impl Wake for CountingWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
     /// Tests that the `new_raised` function returns
     /// a `Gate` that is initially raised,
     /// just like the name / docs claim it does.
@@ -349,6 +1119,7 @@ mod tests {
     /// - even if the `Lever` was dropped! -
     /// as long as the `Gate` was in the appropriate state
     /// when the `Lever` (the only way to change that state) dropped.
+    #[allow(clippy::doc_lazy_continuation)]
     #[test]
     fn ok_even_if_lever_dropped_for_matching_state() {
         let (raised_lever, mut raised_gate) = new_raised();
@@ -405,4 +1176,512 @@ mod tests {
             BeforeGateDropped(Lowered)
         ));
     }
+
+    /// Tests that dropping a `raised` future after it was polled
+    /// doesn't stop a later `raised` call from resolving once the `Lever` raises the `Gate`.
+    #[test]
+    fn raised_is_cancel_safe() {
+        let (lever, mut gate) = new_lowered();
+
+        let mut cancelled = tokio_test::task::spawn(gate.raised());
+        tokio_test::assert_pending!(cancelled.poll());
+        drop(cancelled);
+
+        lever.raise().unwrap();
+
+        tokio_test::assert_ready_ok!(tokio_test::task::spawn(gate.raised()).poll());
+    }
+
+    /// Tests that dropping a `lowered` future after it was polled
+    /// doesn't stop a later `lowered` call from resolving once the `Lever` lowers the `Gate`.
+    #[test]
+    fn lowered_is_cancel_safe() {
+        let (lever, mut gate) = new_raised();
+
+        let mut cancelled = tokio_test::task::spawn(gate.lowered());
+        tokio_test::assert_pending!(cancelled.poll());
+        drop(cancelled);
+
+        lever.lower().unwrap();
+
+        tokio_test::assert_ready_ok!(tokio_test::task::spawn(gate.lowered()).poll());
+    }
+
+    /// Tests that dropping one waiter between polls
+    /// doesn't take the wakeup away from another waiter on a clone of the same `Gate`.
+    #[test]
+    fn cancelled_waiter_does_not_steal_wakeup() {
+        let (lever, mut gate) = new_lowered();
+        let mut other_gate = gate.clone();
+
+        let mut cancelled = tokio_test::task::spawn(gate.raised());
+        let mut kept = tokio_test::task::spawn(other_gate.raised());
+
+        tokio_test::assert_pending!(cancelled.poll());
+        tokio_test::assert_pending!(kept.poll());
+
+        lever.raise().unwrap();
+        drop(cancelled);
+
+        assert!(kept.is_woken());
+        tokio_test::assert_ready_ok!(kept.poll());
+    }
+
+    /// Tests that a `raised` future dropped while the `Lever` raised and lowered the `Gate` again
+    /// doesn't leave the `Gate` believing it already saw the raise:
+    /// a new `raised` call waits for the next raise.
+    #[test]
+    fn cancelled_waiter_does_not_corrupt_seen_state() {
+        let (lever, mut gate) = new_lowered();
+
+        let mut cancelled = tokio_test::task::spawn(gate.raised());
+        tokio_test::assert_pending!(cancelled.poll());
+
+        lever.raise().unwrap();
+        lever.lower().unwrap();
+        drop(cancelled);
+
+        let mut retried = tokio_test::task::spawn(gate.raised());
+        tokio_test::assert_pending!(retried.poll());
+
+        lever.raise().unwrap();
+
+        assert!(retried.is_woken());
+        tokio_test::assert_ready_ok!(retried.poll());
+    }
+
+    /// Tests that a `Gate` stays as small as a handle to its shared state plus one word of bookkeeping,
+    /// since applications keep one around per connection.
+    #[test]
+    fn gate_is_two_pointers_wide() {
+        assert!(std::mem::size_of::<Gate>() <= 2 * std::mem::size_of::<usize>());
+    }
+
+    /// Tests that a burst of toggles wakes a parked waiter only once,
+    /// and that it then sees the final state.
+    #[test]
+    fn burst_of_toggles_wakes_waiter_once() {
+        let (lever, mut gate) = new_lowered();
+
+        let counting_waker = Arc::new(CountingWaker::default());
+        let waker = Waker::from(counting_waker.clone());
+        let mut context = Context::from_waker(&waker);
+
+        let mut waiting = pin!(gate.lowered());
+        lever.raise().unwrap();
+        assert!(waiting.as_mut().poll(&mut context).is_pending());
+
+        for _ in 0..100 {
+            lever.lower().unwrap();
+            lever.raise().unwrap();
+        }
+        lever.lower().unwrap();
+
+        assert_eq!(counting_waker.0.load(Ordering::SeqCst), 1);
+        assert!(waiting.as_mut().poll(&mut context).is_ready());
+    }
+
+    /// Tests that raising a dropped `Gate` panics in strict mode instead of returning an `Err`.
+    #[cfg(all(feature = "strict", debug_assertions))]
+    #[test]
+    #[should_panic(expected = "tried to raise a gate that was dropped")]
+    fn strict_mode_panics_on_raising_dropped_gate() {
+        let (lever, gate) = new_lowered();
+
+        drop(gate);
+
+        let _ = lever.raise();
+    }
+
+    /// Tests that `always_raised` and `always_lowered` gates stay put with their levers alive,
+    /// so waiting for the opposite state pends instead of failing.
+    #[test]
+    fn always_gates_pend_on_opposite_state() {
+        let mut raised_gate = Gate::always_raised();
+        let mut lowered_gate = Gate::always_lowered();
+
+        assert!(raised_gate.is_raised());
+        assert!(lowered_gate.is_lowered());
+        assert!(!raised_gate.lever_was_dropped());
+        assert!(!lowered_gate.lever_was_dropped());
+
+        tokio_test::assert_ready_ok!(tokio_test::task::spawn(raised_gate.raised()).poll());
+        tokio_test::assert_pending!(tokio_test::task::spawn(raised_gate.lowered()).poll());
+        tokio_test::assert_ready_ok!(tokio_test::task::spawn(lowered_gate.lowered()).poll());
+        tokio_test::assert_pending!(tokio_test::task::spawn(lowered_gate.raised()).poll());
+    }
+
+    /// Tests that a `frozen` gate fails immediately when waited on for the opposite state.
+    #[test]
+    fn frozen_gate_errs_on_opposite_state() {
+        let mut gate = Gate::frozen(Raised);
+
+        assert!(gate.is_raised());
+        assert!(gate.lever_was_dropped());
+
+        tokio_test::assert_ready_ok!(tokio_test::task::spawn(gate.raised()).poll());
+        tokio_test::assert_ready_err!(tokio_test::task::spawn(gate.lowered()).poll());
+    }
+
+    /// Tests that every transition (and only a transition) starts a new epoch,
+    /// seen the same from the lever and the gate.
+    #[test]
+    fn transitions_start_new_epochs() {
+        let (lever, gate) = new_lowered();
+        assert_eq!(gate.current_epoch(), 0);
+
+        lever.lower().unwrap();
+        assert_eq!(gate.current_epoch(), 0);
+
+        lever.raise().unwrap();
+        lever.lower().unwrap();
+        assert_eq!(gate.current_epoch(), 2);
+        assert_eq!(lever.current_epoch(), 2);
+    }
+
+    /// Tests that waiting for a raise after an epoch ignores the raise the gate is already in,
+    /// and finishes with the next one.
+    #[test]
+    fn raised_in_epoch_after_ignores_earlier_raise() {
+        let (lever, mut gate) = new_raised();
+        let epoch = gate.current_epoch();
+
+        let mut waiting = tokio_test::task::spawn(gate.raised_in_epoch_after(epoch));
+        tokio_test::assert_pending!(waiting.poll());
+
+        lever.lower().unwrap();
+        tokio_test::assert_pending!(waiting.poll());
+
+        lever.raise().unwrap();
+        assert_eq!(tokio_test::assert_ready_ok!(waiting.poll()), 2);
+    }
+
+    /// Tests that waiting for a lower after an epoch fails if the lever drops first,
+    /// even though the gate is lowered.
+    #[test]
+    fn lowered_in_epoch_after_fails_on_lever_drop() {
+        let (lever, mut gate) = new_lowered();
+
+        drop(lever);
+
+        tokio_test::assert_ready_err!(
+            tokio_test::task::spawn(gate.lowered_in_epoch_after(0)).poll()
+        );
+    }
+
+    /// Tests that waiting for the next lower skips the lower the gate is already in.
+    #[test]
+    fn next_lower_is_edge_triggered() {
+        let (lever, mut gate) = new_lowered();
+
+        let mut waiting = tokio_test::task::spawn(gate.next_lower());
+        tokio_test::assert_pending!(waiting.poll());
+
+        lever.raise().unwrap();
+        tokio_test::assert_pending!(waiting.poll());
+
+        lever.lower().unwrap();
+        assert_eq!(tokio_test::assert_ready_ok!(waiting.poll()), 2);
+    }
+
+    /// Tests that the next raise counts from when it was called, not from when it was first polled.
+    #[test]
+    fn next_raise_counts_from_call() {
+        let (lever, mut gate) = new_lowered();
+
+        let mut waiting = tokio_test::task::spawn(gate.next_raise());
+        lever.raise().unwrap();
+        assert_eq!(tokio_test::assert_ready_ok!(waiting.poll()), 1);
+    }
+
+    /// Tests that waiting for a condition checks the current state, then every change, until the lever drops.
+    #[test]
+    fn wait_for_checks_every_state() {
+        let (lever, mut gate) = new_lowered();
+        let mut seen = Vec::new();
+
+        let mut waiting = tokio_test::task::spawn(gate.wait_for(|gateway| {
+            seen.push(gateway);
+            false
+        }));
+        tokio_test::assert_pending!(waiting.poll());
+
+        lever.raise().unwrap();
+        tokio_test::assert_pending!(waiting.poll());
+
+        drop(lever);
+        tokio_test::assert_ready_err!(waiting.poll());
+        drop(waiting);
+        assert_eq!(seen, [Lowered, Raised]);
+
+        let (_lever, mut gate) = new_raised();
+        assert_eq!(
+            tokio_test::assert_ready_ok!(tokio_test::task::spawn(
+                gate.wait_for(|gateway| gateway == Raised)
+            )
+            .poll()),
+            Raised
+        );
+    }
+
+    /// Tests that following a gate mirrors its state until its lever is dropped.
+    #[test]
+    fn follow_mirrors_source() {
+        let (source_lever, source) = new_raised();
+        let (lever, gate) = new_lowered();
+
+        let mut following = tokio_test::task::spawn(lever.follow(source));
+        tokio_test::assert_pending!(following.poll());
+        assert!(gate.is_raised());
+
+        source_lever.lower().unwrap();
+        tokio_test::assert_pending!(following.poll());
+        assert!(gate.is_lowered());
+
+        source_lever.raise().unwrap();
+        drop(source_lever);
+        tokio_test::assert_ready!(following.poll());
+        assert!(gate.is_raised());
+    }
+
+    /// Tests that following stops once nobody is looking at the following gate.
+    #[test]
+    fn follow_stops_when_gates_dropped() {
+        let (_source_lever, source) = new_raised();
+        let (lever, gate) = new_lowered();
+
+        let mut following = tokio_test::task::spawn(lever.follow(source));
+        tokio_test::assert_pending!(following.poll());
+
+        drop(gate);
+        tokio_test::assert_ready!(following.poll());
+    }
+
+    /// Tests that a gateway is serialized as the same string it's parsed from.
+    #[cfg(feature = "serde")]
+    #[test]
+    fn gateway_serializes_as_string() {
+        assert_eq!(serde_json::to_string(&Raised).unwrap(), r#""Raised""#);
+        assert_eq!(
+            serde_json::from_str::<Gateway>(r#""Lowered""#).unwrap(),
+            Lowered
+        );
+        assert!(serde_json::from_str::<Gateway>(r#""raised""#).is_err());
+    }
+
+    /// Tests that gateways convert to and from booleans, with `true` meaning raised.
+    #[test]
+    fn converts_bools() {
+        assert_eq!(Gateway::from(true), Raised);
+        assert!(!bool::from(Lowered));
+
+        let (lever, gate) = new_from_bool(false);
+        assert!(gate.is_lowered());
+        assert!(!lever.set_bool(true).unwrap());
+        assert!(gate.is_raised());
+    }
+
+    /// Tests that resetting a gate puts it back in its initial state and epoch.
+    #[test]
+    fn reset_clears_history() {
+        let (lever, mut gate) = new_lowered();
+        lever.raise().unwrap();
+        lever.lower().unwrap();
+        lever.raise().unwrap();
+
+        gate.reset(&lever, Lowered);
+        assert!(gate.is_lowered());
+        assert_eq!(gate.current_epoch(), 0);
+
+        lever.raise().unwrap();
+        assert_eq!(gate.current_epoch(), 1);
+    }
+
+    /// Tests that a gate can't be reset with some other lever.
+    #[test]
+    #[should_panic = "its own lever"]
+    fn reset_checks_lever() {
+        let (_, mut gate) = new_lowered();
+        let (other_lever, _other_gate) = new_lowered();
+
+        gate.reset(&other_lever, Raised);
+    }
+
+    /// Tests that a transaction is observed as a single transition,
+    /// and not at all if it ends where it started.
+    #[test]
+    fn transaction_is_one_transition() {
+        let (lever, gate) = new_lowered();
+        let mut waiter = gate.clone();
+        let mut waiting = tokio_test::task::spawn(waiter.lowered_in_epoch_after(0));
+        tokio_test::assert_pending!(waiting.poll());
+
+        let flips = lever
+            .transaction(|gateway| {
+                *gateway = Raised;
+                *gateway = !*gateway;
+                2
+            })
+            .unwrap();
+        assert_eq!(flips, 2);
+        assert_eq!(gate.current_epoch(), 0);
+        assert!(!waiting.is_woken());
+
+        lever.transaction(|gateway| *gateway = Raised).unwrap();
+        assert_eq!(gate.current_epoch(), 1);
+        assert!(waiting.is_woken());
+        tokio_test::assert_pending!(waiting.poll());
+    }
+
+    /// Tests that awaiting a mutable reference to a gate waits for it to be raised.
+    #[test]
+    fn await_mut_gate_waits_for_raise() {
+        let (lever, mut gate) = new_lowered();
+
+        let mut waiting = tokio_test::task::spawn(async { (&mut gate).await });
+        tokio_test::assert_pending!(waiting.poll());
+
+        lever.raise().unwrap();
+        tokio_test::assert_ready_ok!(waiting.poll());
+    }
+
+    /// Tests that many waits can share one gate through a shared reference.
+    #[test]
+    fn shared_waits() {
+        let (lever, gate) = new_lowered();
+        let gate = Arc::new(gate);
+
+        let mut first = tokio_test::task::spawn(gate.raised_shared());
+        let mut second = tokio_test::task::spawn(gate.raised_shared());
+        tokio_test::assert_pending!(first.poll());
+        tokio_test::assert_pending!(second.poll());
+
+        lever.raise().unwrap();
+        tokio_test::assert_ready_ok!(first.poll());
+        tokio_test::assert_ready_ok!(second.poll());
+        tokio_test::assert_pending!(tokio_test::task::spawn(gate.lowered_shared()).poll());
+    }
+
+    /// Tests that an owned wait can be spawned onto a runtime.
+    #[tokio::test]
+    async fn owned_wait_can_be_spawned() {
+        let (lever, gate) = new_raised();
+
+        let waiting = tokio::spawn(gate.lowered_owned());
+        lever.lower().unwrap();
+
+        waiting.await.unwrap().unwrap();
+    }
+
+    /// Tests that states sent into a lever move its gate.
+    #[test]
+    fn lever_is_a_sink() {
+        use futures_sink::Sink;
+
+        let (mut lever, gate) = new_lowered();
+        let mut sending = tokio_test::task::spawn(());
+
+        tokio_test::assert_ready_ok!(sending.enter(|cx, _| Pin::new(&mut lever).poll_ready(cx)));
+        Pin::new(&mut lever).start_send(Raised).unwrap();
+        assert!(gate.is_raised());
+
+        drop(gate);
+        tokio_test::assert_ready_err!(sending.enter(|cx, _| Pin::new(&mut lever).poll_ready(cx)));
+    }
+
+    /// Tests that toggling flips the gate and returns the new state.
+    #[test]
+    fn toggle_flips() {
+        let (lever, gate) = new_lowered();
+
+        assert_eq!(lever.toggle().unwrap(), Raised);
+        assert!(gate.is_raised());
+        assert_eq!(lever.toggle().unwrap(), Lowered);
+        assert_eq!(gate.current_epoch(), 2);
+    }
+
+    /// Tests that setting the gate returns the state it was in before.
+    #[test]
+    fn set_returns_previous() {
+        let (lever, gate) = new_lowered();
+
+        assert_eq!(lever.set(Raised).unwrap(), Lowered);
+        assert_eq!(lever.set(Raised).unwrap(), Raised);
+        assert!(gate.is_raised());
+        assert_eq!(gate.current_epoch(), 1);
+    }
+
+    /// Tests that raising and lowering report whether they changed the gate.
+    #[test]
+    fn transitions_report_changes() {
+        let (lever, _gate) = new_lowered();
+
+        assert!(lever.raise().unwrap());
+        assert!(!lever.raise().unwrap());
+        assert!(lever.lower().unwrap());
+        assert!(!lever.lower().unwrap());
+    }
+
+    /// Tests that the gate only counts the lever as dropped once every clone is dropped.
+    #[test]
+    fn cloned_levers_share_gate() {
+        let (lever, gate) = new_lowered();
+        let other_lever = lever.clone();
+
+        other_lever.raise().unwrap();
+        assert!(gate.is_raised());
+
+        drop(lever);
+        assert!(!gate.lever_was_dropped());
+        assert!(other_lever.lower().unwrap());
+
+        drop(other_lever);
+        assert!(gate.lever_was_dropped());
+    }
+
+    /// Tests that a lever can create new gates, even after every gate was dropped.
+    #[test]
+    fn subscribe_creates_gates() {
+        let (lever, gate) = new_lowered();
+        drop(gate);
+        assert!(lever.gate_was_dropped());
+
+        let gate = lever.subscribe();
+        assert!(!lever.gate_was_dropped());
+        lever.raise().unwrap();
+        assert!(gate.is_raised());
+    }
+
+    /// Tests that the gate moves to the configured state once every lever is dropped.
+    #[test]
+    fn on_drop_moves_gate() {
+        let (lever, mut gate) = new_raised();
+        lever.on_drop(Lowered);
+
+        let mut waiting = tokio_test::task::spawn(gate.lowered());
+        tokio_test::assert_pending!(waiting.poll());
+
+        let other_lever = lever.clone();
+        drop(lever);
+        tokio_test::assert_pending!(waiting.poll());
+
+        drop(other_lever);
+        tokio_test::assert_ready_ok!(waiting.poll());
+    }
+
+    /// Tests that waiting for the gates to be dropped finishes once the last one is dropped.
+    #[test]
+    fn closed_waits_for_every_gate() {
+        let (lever, gate) = new_lowered();
+        let other_gate = gate.clone();
+
+        let mut closing = tokio_test::task::spawn(lever.closed());
+        tokio_test::assert_pending!(closing.poll());
+
+        drop(gate);
+        tokio_test::assert_pending!(closing.poll());
+
+        drop(other_gate);
+        tokio_test::assert_ready!(closing.poll());
+    }
 }
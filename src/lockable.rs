@@ -0,0 +1,152 @@
+//! Levers with a third position, [`Locked`](Position::Locked), for maintenance modes:
+//! a locked gate is lowered and can't be raised again until it's unlocked.
+//!
+//! Gates don't know about locking, so waiters see a locked gate as lowered
+//! and keep waiting for it to be raised after it's unlocked.
+
+use std::sync::Mutex;
+
+use thiserror::Error;
+
+use crate::{Gate, GateDropped, Gateway, Lever, Lowered, Raised};
+
+/// Where a [`LockableLever`] is
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Position {
+    /// The gate is raised
+    Raised,
+    /// The gate is lowered, but can be raised
+    Lowered,
+    /// The gate is lowered and can't be raised until the lever is unlocked
+    Locked,
+}
+
+/// The gate couldn't be raised
+#[derive(Debug, Error)]
+pub enum LockedRaiseError {
+    /// The lever is locked, so the gate was left lowered
+    #[error("lever is locked")]
+    Locked,
+    /// The gate was dropped
+    #[error(transparent)]
+    GateDropped(#[from] GateDropped),
+}
+
+/// A lever that can be locked in the lowered position
+pub struct LockableLever {
+    lever: Lever,
+    /// Whether the lever is locked, held while raising so that locking can't slip in between the check and the raise
+    locked: Mutex<bool>,
+}
+
+impl LockableLever {
+    /// Make the given `lever` lockable, starting out unlocked.
+    ///
+    /// Raising through the `lever` itself (or a clone of it) bypasses the lock,
+    /// so it should only be used through this from now on.
+    #[must_use]
+    pub fn new(lever: Lever) -> Self {
+        Self {
+            lever,
+            locked: Mutex::new(false),
+        }
+    }
+
+    /// Raise the gate, unless the lever is locked.
+    /// Returns whether that changed the gate's state.
+    /// # Errors
+    /// If the lever is locked, `Err(LockedRaiseError::Locked)` is returned and the gate stays lowered.
+    /// If the gate was dropped, `Err(LockedRaiseError::GateDropped)` is returned.
+    pub fn raise(&self) -> Result<bool, LockedRaiseError> {
+        let locked = self.locked.lock().unwrap();
+        if *locked {
+            return Err(LockedRaiseError::Locked);
+        }
+
+        Ok(self.lever.raise()?)
+    }
+
+    /// Lower the gate (whether or not the lever is locked).
+    /// Returns whether that changed the gate's state.
+    /// # Errors
+    /// If the gate was dropped, an `Err` is returned.
+    pub fn lower(&self) -> Result<bool, GateDropped> {
+        self.lever.lower()
+    }
+
+    /// Lower the gate and lock the lever so it can't be raised until [`unlock`](LockableLever::unlock) is called.
+    /// Returns whether the lever wasn't already locked.
+    /// # Errors
+    /// If the gate was dropped, an `Err` is returned (but the lever is still locked).
+    pub fn lock(&self) -> Result<bool, GateDropped> {
+        let mut locked = self.locked.lock().unwrap();
+        let newly_locked = !*locked;
+        *locked = true;
+
+        self.lever.lower()?;
+        Ok(newly_locked)
+    }
+
+    /// Unlock the lever, leaving the gate lowered until it's raised again.
+    /// Returns whether the lever was locked.
+    pub fn unlock(&self) -> bool {
+        std::mem::replace(&mut *self.locked.lock().unwrap(), false)
+    }
+
+    /// Returns whether the lever is locked.
+    #[must_use]
+    pub fn is_locked(&self) -> bool {
+        *self.locked.lock().unwrap()
+    }
+
+    /// Returns where the lever is.
+    #[must_use]
+    pub fn position(&self) -> Position {
+        let locked = self.locked.lock().unwrap();
+
+        match (*locked, self.lever.sender.borrow().gateway) {
+            (true, _) => Position::Locked,
+            (false, Raised) => Position::Raised,
+            (false, Lowered) => Position::Lowered,
+        }
+    }
+
+    /// Create a new gate observing this lever, like [`Lever::subscribe`].
+    #[must_use]
+    pub fn subscribe(&self) -> Gate {
+        self.lever.subscribe()
+    }
+}
+
+/// Create a gate controlled by the returned [`LockableLever`], starting unlocked in the `initial` state.
+#[must_use]
+pub fn new(initial: Gateway) -> (LockableLever, Gate) {
+    let (lever, gate) = crate::new(initial);
+
+    (LockableLever::new(lever), gate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests that a locked lever lowers the gate and refuses to raise it until unlocked.
+    #[test]
+    fn locked_lever_refuses_raise() {
+        let (lever, gate) = new(Raised);
+        assert_eq!(lever.position(), Position::Raised);
+
+        assert!(lever.lock().unwrap());
+        assert!(gate.is_lowered());
+        assert_eq!(lever.position(), Position::Locked);
+        assert!(!lever.lock().unwrap());
+
+        assert!(matches!(lever.raise(), Err(LockedRaiseError::Locked)));
+        assert!(gate.is_lowered());
+
+        assert!(lever.unlock());
+        assert_eq!(lever.position(), Position::Lowered);
+        assert!(lever.raise().unwrap());
+        assert!(gate.is_raised());
+    }
+}
@@ -0,0 +1,271 @@
+//! Gates whose state survives a restart of the process (like a kill switch),
+//! by saving every transition to a [`StateStore`] before making it.
+//!
+//! This module is only available with the `persist` feature.
+
+use std::{
+    fs, io,
+    path::PathBuf,
+    sync::Mutex,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use thiserror::Error;
+
+use crate::{misuse, with_state, Gate, GateDropped, Gateway, Lever, Lowered, Raised, State};
+
+/// The saved state of a gate, and when it last changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PersistedState {
+    /// The state the gate is in
+    pub gateway: Gateway,
+    /// The gate's epoch (see [`Gate::current_epoch`])
+    pub epoch: u64,
+    /// When the gate moved to this state
+    pub changed_at: SystemTime,
+}
+
+/// Somewhere to save a gate's state, so it can be restored after a restart
+pub trait StateStore {
+    /// Load the saved state, or `Ok(None)` if nothing was saved yet.
+    /// # Errors
+    /// If the state couldn't be loaded, an `Err` is returned.
+    fn load(&mut self) -> io::Result<Option<PersistedState>>;
+
+    /// Save the `state` (replacing what was saved before).
+    /// This must only return once the state is durable.
+    /// # Errors
+    /// If the state couldn't be saved, an `Err` is returned.
+    fn save(&mut self, state: &PersistedState) -> io::Result<()>;
+}
+
+/// A [`StateStore`] that saves to a file
+///
+/// Saving writes a temporary file next to it and renames it into place,
+/// so a crash while saving leaves the previous state intact.
+#[derive(Debug, Clone)]
+pub struct FileStore {
+    path: PathBuf,
+}
+
+impl FileStore {
+    /// Save to (and load from) the file at `path`.
+    #[must_use]
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+impl StateStore for FileStore {
+    fn load(&mut self) -> io::Result<Option<PersistedState>> {
+        let contents = match fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(error),
+        };
+
+        let mut fields = contents.split_whitespace();
+        let mut next = |name| fields.next().ok_or_else(|| invalid_data(name));
+
+        let gateway = next("missing gateway")?
+            .parse()
+            .map_err(|_| invalid_data("invalid gateway"))?;
+        let epoch = next("missing epoch")?
+            .parse()
+            .map_err(|_| invalid_data("invalid epoch"))?;
+        let nanos: u64 = next("missing change time")?
+            .parse()
+            .map_err(|_| invalid_data("invalid change time"))?;
+
+        Ok(Some(PersistedState {
+            gateway,
+            epoch,
+            changed_at: UNIX_EPOCH + Duration::from_nanos(nanos),
+        }))
+    }
+
+    fn save(&mut self, state: &PersistedState) -> io::Result<()> {
+        let nanos = state
+            .changed_at
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_nanos());
+
+        let mut temporary = self.path.clone().into_os_string();
+        temporary.push(".tmp");
+
+        let file = fs::File::create(&temporary)?;
+        io::Write::write_all(
+            &mut &file,
+            format!("{} {} {nanos}\n", state.gateway, state.epoch).as_bytes(),
+        )?;
+        file.sync_all()?;
+
+        fs::rename(&temporary, &self.path)
+    }
+}
+
+/// Changing a persistent gate didn't succeed
+#[derive(Debug, Error)]
+pub enum PersistError {
+    /// The gate was dropped, so changing it achieves nothing
+    #[error(transparent)]
+    GateDropped(#[from] GateDropped),
+    /// The new state couldn't be saved, so the gate wasn't changed
+    #[error("couldn't save the gate's state")]
+    Io(#[from] io::Error),
+}
+
+/// A lever that saves every transition to its [`StateStore`] before making it
+pub struct PersistentLever<S> {
+    lever: Lever,
+    /// The store, and the last state saved to it (or loaded from it)
+    store: Mutex<(S, Option<PersistedState>)>,
+}
+
+impl<S: StateStore> PersistentLever<S> {
+    fn set_gateway(&self, gateway: Gateway) -> Result<bool, PersistError> {
+        if self.lever.gate_was_dropped() {
+            misuse("tried to change a gate that was dropped");
+            return Err(GateDropped.into());
+        }
+
+        let mut store = self.store.lock().unwrap();
+        let (store, last_change) = &mut *store;
+
+        let (current, epoch) = {
+            let state = self.lever.sender.borrow();
+            (state.gateway, state.epoch)
+        };
+        if current == gateway {
+            return Ok(false);
+        }
+
+        let change = PersistedState {
+            gateway,
+            epoch: epoch + 1,
+            changed_at: SystemTime::now(),
+        };
+        store.save(&change)?;
+
+        self.lever.set_gateway(gateway);
+        *last_change = Some(change);
+
+        Ok(true)
+    }
+
+    /// Save that the gate is raised, then raise it (see [`Lever::raise`]),
+    /// returning whether it was lowered before.
+    /// If it's already raised, nothing is saved.
+    /// # Errors
+    /// If the gate was dropped or saving failed, an `Err` is returned and the gate isn't changed.
+    /// # Panics
+    /// With the `strict` feature enabled in debug builds, this panics instead of returning an `Err` if the gate was dropped.
+    pub fn raise(&self) -> Result<bool, PersistError> {
+        self.set_gateway(Raised)
+    }
+
+    /// Save that the gate is lowered, then lower it (see [`Lever::lower`]),
+    /// returning whether it was raised before.
+    /// If it's already lowered, nothing is saved.
+    /// # Errors
+    /// If the gate was dropped or saving failed, an `Err` is returned and the gate isn't changed.
+    /// # Panics
+    /// With the `strict` feature enabled in debug builds, this panics instead of returning an `Err` if the gate was dropped.
+    pub fn lower(&self) -> Result<bool, PersistError> {
+        self.set_gateway(Lowered)
+    }
+
+    /// Returns the last saved change (including one restored from before a restart),
+    /// or `None` if the gate hasn't changed since it was first created.
+    #[must_use]
+    pub fn last_change(&self) -> Option<PersistedState> {
+        self.store.lock().unwrap().1
+    }
+
+    /// Returns the lever being persisted.
+    ///
+    /// Changes made with it directly aren't saved.
+    #[must_use]
+    pub fn lever(&self) -> &Lever {
+        &self.lever
+    }
+}
+
+/// Create a gate in the state saved in the `store` (or in the `initial` state if nothing was saved yet),
+/// whose returned [`PersistentLever`] saves every transition to the `store`.
+/// # Errors
+/// If the saved state couldn't be loaded, an `Err` is returned.
+pub fn restore<S: StateStore>(
+    mut store: S,
+    initial: Gateway,
+) -> io::Result<(PersistentLever<S>, Gate)> {
+    let last_change = store.load()?;
+
+    let state = match last_change {
+        Some(saved) => State {
+            epoch: saved.epoch,
+            ..State::new(saved.gateway)
+        },
+        None => State::new(initial),
+    };
+    let (lever, gate) = with_state(state);
+
+    let lever = PersistentLever {
+        lever,
+        store: Mutex::new((store, last_change)),
+    };
+
+    Ok((lever, gate))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests that a gate restored from a file is in the state it was left in.
+    #[test]
+    fn restores_from_file() {
+        let path = std::env::temp_dir().join(format!("async-gate-{}", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let (lever, gate) = restore(FileStore::new(&path), Lowered).unwrap();
+        assert!(gate.is_lowered());
+        assert_eq!(lever.last_change(), None);
+        lever.raise().unwrap();
+        lever.lower().unwrap();
+        lever.raise().unwrap();
+        let last_change = lever.last_change().unwrap();
+        drop((lever, gate));
+
+        let (lever, gate) = restore(FileStore::new(&path), Lowered).unwrap();
+        assert!(gate.is_raised());
+        assert_eq!(gate.current_epoch(), 3);
+        assert_eq!(lever.last_change(), Some(last_change));
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    /// Tests that a gate isn't changed if saving the change fails.
+    #[test]
+    fn unchanged_if_saving_fails() {
+        struct Broken;
+
+        impl StateStore for Broken {
+            fn load(&mut self) -> io::Result<Option<PersistedState>> {
+                Ok(None)
+            }
+
+            fn save(&mut self, _: &PersistedState) -> io::Result<()> {
+                Err(io::ErrorKind::Other.into())
+            }
+        }
+
+        let (lever, gate) = restore(Broken, Lowered).unwrap();
+        assert!(matches!(lever.raise(), Err(PersistError::Io(_))));
+        assert!(gate.is_lowered());
+    }
+}
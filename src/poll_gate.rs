@@ -0,0 +1,157 @@
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use pin_project_lite::pin_project;
+use tokio::sync::futures::OwnedNotified;
+
+use crate::{
+    wait::poll_gateway, Gate, LeverDroppedWhileLowered, LeverDroppedWhileRaised, Lowered, Raised,
+};
+
+pin_project! {
+    /// A gate that can be waited on by polling, for places that can't `.await`,
+    /// like `tower::Service::poll_ready` or a handwritten `Future`
+    ///
+    /// It keeps track of the wait between calls to [`poll_raised`] or [`poll_lowered`],
+    /// so calling them again and again (with the same or different tasks) is correct
+    /// and only the most recent task is woken.
+    ///
+    /// It keeps its registration for wakeups inline (like the futures of [`Gate::raised`] and [`Gate::lowered`]),
+    /// so polling never allocates, but it has to be pinned first:
+    /// like with [`std::pin::pin!`], as a pinned field of a handwritten future, or with [`Box::pin`] once up front.
+    ///
+    /// [`poll_raised`]: PollGate::poll_raised
+    /// [`poll_lowered`]: PollGate::poll_lowered
+    pub struct PollGate {
+        gate: Gate,
+        #[pin]
+        notified: Option<OwnedNotified>,
+    }
+}
+
+impl PollGate {
+    /// Wrap the given `gate` so it can be polled.
+    #[must_use]
+    pub fn new(gate: Gate) -> Self {
+        Self {
+            gate,
+            notified: None,
+        }
+    }
+
+    /// Poll for the gate to be raised: returning `Poll::Ready(Ok(()))` if it is
+    /// and otherwise returning `Poll::Pending` and waking the task of `cx` once it is.
+    ///
+    /// This doesn't allocate.
+    /// # Errors
+    /// If the lever is dropped while the gate is lowered, `Poll::Ready(Err(...))` is returned.
+    pub fn poll_raised(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), LeverDroppedWhileLowered>> {
+        let this = self.project();
+
+        poll_gateway(this.gate, this.notified, Raised, cx).map(|raised| {
+            if raised {
+                Ok(())
+            } else {
+                Err(LeverDroppedWhileLowered)
+            }
+        })
+    }
+
+    /// Poll for the gate to be lowered: returning `Poll::Ready(Ok(()))` if it is
+    /// and otherwise returning `Poll::Pending` and waking the task of `cx` once it is.
+    ///
+    /// This doesn't allocate.
+    /// # Errors
+    /// If the lever is dropped while the gate is raised, `Poll::Ready(Err(...))` is returned.
+    pub fn poll_lowered(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), LeverDroppedWhileRaised>> {
+        let this = self.project();
+
+        poll_gateway(this.gate, this.notified, Lowered, cx).map(|lowered| {
+            if lowered {
+                Ok(())
+            } else {
+                Err(LeverDroppedWhileRaised)
+            }
+        })
+    }
+
+    /// Returns the gate being polled.
+    #[must_use]
+    pub fn gate(&self) -> &Gate {
+        &self.gate
+    }
+
+    /// Stop polling and get the gate back.
+    #[must_use]
+    pub fn into_inner(self) -> Gate {
+        self.gate
+    }
+}
+
+impl From<Gate> for PollGate {
+    fn from(gate: Gate) -> Self {
+        Self::new(gate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{future::poll_fn, pin::pin};
+
+    use tokio_test::{assert_pending, assert_ready_err, assert_ready_ok, task};
+
+    use super::*;
+
+    /// Tests that polling again and again waits for the same raise and wakes the task.
+    #[test]
+    fn repeated_polls_wait_for_raise() {
+        let (lever, gate) = crate::new_lowered();
+        let mut gate = pin!(PollGate::new(gate));
+
+        let mut waiting = task::spawn(());
+        assert_pending!(waiting.enter(|cx, _| gate.as_mut().poll_raised(cx)));
+        assert_pending!(waiting.enter(|cx, _| gate.as_mut().poll_raised(cx)));
+
+        lever.raise().unwrap();
+        assert!(waiting.is_woken());
+        assert_ready_ok!(waiting.enter(|cx, _| gate.as_mut().poll_raised(cx)));
+        assert_pending!(waiting.enter(|cx, _| gate.as_mut().poll_lowered(cx)));
+    }
+
+    /// Tests that polling from a different task moves the wakeup to that task.
+    #[test]
+    fn wakes_most_recent_task() {
+        let (lever, gate) = crate::new_lowered();
+        let mut gate = pin!(PollGate::new(gate));
+
+        let mut first = task::spawn(());
+        let mut second = task::spawn(());
+        assert_pending!(first.enter(|cx, _| gate.as_mut().poll_raised(cx)));
+        assert_pending!(second.enter(|cx, _| gate.as_mut().poll_raised(cx)));
+
+        lever.raise().unwrap();
+        assert!(!first.is_woken());
+        assert!(second.is_woken());
+    }
+
+    /// Tests that dropping the lever makes polling fail.
+    #[test]
+    fn lever_dropped() {
+        let (lever, gate) = crate::new_raised();
+        let mut gate = pin!(PollGate::new(gate));
+
+        let mut waiting = task::spawn(poll_fn(|cx| gate.as_mut().poll_lowered(cx)));
+        assert_pending!(waiting.poll());
+
+        drop(lever);
+        assert_ready_err!(waiting.poll());
+    }
+}
@@ -0,0 +1,153 @@
+//! Recording what a lever does (and when) so it can be replayed later,
+//! to reproduce a sequence of transitions seen in production in a test.
+//!
+//! This module is only available with the `time` feature.
+
+use std::{sync::Mutex, time::Duration};
+
+use tokio::time::Instant;
+
+use crate::{GateDropped, Gateway, Lever, Lowered, Raised};
+
+/// A recorded sequence of transitions, each with the time since the one before it
+/// (or since recording started, for the first one)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Recording {
+    steps: Vec<(Duration, Gateway)>,
+}
+
+impl Recording {
+    /// Returns each recorded operation with the time since the one before it.
+    /// These can be passed to `test_util::ScriptedLever` (with the `test-util` feature).
+    #[must_use]
+    pub fn steps(&self) -> &[(Duration, Gateway)] {
+        &self.steps
+    }
+
+    /// Drive the given `lever` through the same operations with the same timing, on Tokio's clock,
+    /// but with every delay multiplied by `time_scale` (so 0.5 replays twice as fast and 0.0 replays instantly).
+    /// # Errors
+    /// If the gate is dropped before the replay finishes, an `Err` is returned.
+    /// # Panics
+    /// Panics if `time_scale` is negative or not finite.
+    pub async fn replay(&self, lever: &Lever, time_scale: f64) -> Result<(), GateDropped> {
+        for (delay, gateway) in &self.steps {
+            tokio::time::sleep(delay.mul_f64(time_scale)).await;
+
+            lever.set(*gateway)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromIterator<(Duration, Gateway)> for Recording {
+    fn from_iter<I: IntoIterator<Item = (Duration, Gateway)>>(steps: I) -> Self {
+        Self {
+            steps: steps.into_iter().collect(),
+        }
+    }
+}
+
+/// A lever that records every operation done with it, with Tokio's clock
+pub struct RecordingLever {
+    lever: Lever,
+    /// When the last operation happened and everything recorded so far
+    recorded: Mutex<(Instant, Recording)>,
+}
+
+impl RecordingLever {
+    /// Start recording operations done with the given `lever` through the returned `RecordingLever`.
+    #[must_use]
+    pub fn new(lever: Lever) -> Self {
+        Self {
+            lever,
+            recorded: Mutex::new((Instant::now(), Recording::default())),
+        }
+    }
+
+    fn record(&self, gateway: Gateway) {
+        let mut recorded = self.recorded.lock().unwrap();
+        let (last, recording) = &mut *recorded;
+
+        let now = Instant::now();
+        recording.steps.push((now - *last, gateway));
+        *last = now;
+    }
+
+    /// Raise the gate (see [`Lever::raise`]) and record that,
+    /// returning whether the gate was lowered before.
+    /// # Errors
+    /// If the gate was dropped, an `Err` is returned (and nothing is recorded).
+    pub fn raise(&self) -> Result<bool, GateDropped> {
+        let changed = self.lever.raise()?;
+        self.record(Raised);
+        Ok(changed)
+    }
+
+    /// Lower the gate (see [`Lever::lower`]) and record that,
+    /// returning whether the gate was raised before.
+    /// # Errors
+    /// If the gate was dropped, an `Err` is returned (and nothing is recorded).
+    pub fn lower(&self) -> Result<bool, GateDropped> {
+        let changed = self.lever.lower()?;
+        self.record(Lowered);
+        Ok(changed)
+    }
+
+    /// Returns the lever being recorded.
+    #[must_use]
+    pub fn lever(&self) -> &Lever {
+        &self.lever
+    }
+
+    /// Returns what has been recorded so far.
+    #[must_use]
+    pub fn recording(&self) -> Recording {
+        self.recorded.lock().unwrap().1.clone()
+    }
+
+    /// Stop recording, returning the lever and what was recorded.
+    #[must_use]
+    pub fn into_inner(self) -> (Lever, Recording) {
+        let (_, recording) = self.recorded.into_inner().unwrap();
+        (self.lever, recording)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests that replaying a recording at half the time scale repeats the same transitions twice as fast.
+    #[tokio::test(start_paused = true)]
+    async fn replays_recording_scaled() {
+        let (lever, _gate) = crate::new_lowered();
+        let lever = RecordingLever::new(lever);
+
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        lever.raise().unwrap();
+        tokio::time::sleep(Duration::from_secs(4)).await;
+        lever.lower().unwrap();
+
+        let (_, recording) = lever.into_inner();
+        assert_eq!(
+            recording.steps(),
+            [
+                (Duration::from_secs(2), Raised),
+                (Duration::from_secs(4), Lowered)
+            ]
+        );
+
+        let (lever, gate) = crate::new_lowered();
+        let replay = tokio::spawn(async move { recording.replay(&lever, 0.5).await });
+
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        assert!(gate.is_lowered());
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        assert!(gate.is_raised());
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        assert!(gate.is_lowered());
+        replay.await.unwrap().unwrap();
+    }
+}
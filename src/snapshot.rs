@@ -0,0 +1,85 @@
+use crate::{Gate, Gateway, Lever};
+
+/// The status of a gate at one moment, for reporting it somewhere structured (like a debug endpoint)
+///
+/// With the `serde` feature, this can be serialized and deserialized.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GateSnapshot {
+    /// The state the gate was in
+    pub gateway: Gateway,
+    /// How many times the gate had changed state (see [`Gate::current_epoch`])
+    pub epoch: u64,
+    /// Whether the lever hadn't been dropped
+    pub lever_alive: bool,
+    /// How many gates there were, if the snapshot was taken from the lever
+    /// (a gate can't tell how many others there are, so this is `None` in a snapshot taken from one)
+    pub gate_count: Option<usize>,
+}
+
+impl Lever {
+    /// Returns a snapshot of the gate's status.
+    #[must_use]
+    pub fn snapshot(&self) -> GateSnapshot {
+        let state = self.sender.borrow();
+
+        GateSnapshot {
+            gateway: state.gateway,
+            epoch: state.epoch,
+            lever_alive: true,
+            gate_count: Some(self.sender.receiver_count()),
+        }
+    }
+}
+
+impl Gate {
+    /// Returns a snapshot of the gate's status.
+    #[must_use]
+    pub fn snapshot(&self) -> GateSnapshot {
+        let lever_alive = !self.lever_was_dropped();
+        let state = self.receiver.borrow();
+
+        GateSnapshot {
+            gateway: state.gateway,
+            epoch: state.epoch,
+            lever_alive,
+            gate_count: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{new_lowered, Lowered, Raised};
+
+    /// Tests that snapshots from either side report the gate's status.
+    #[test]
+    fn reports_status() {
+        let (lever, gate) = new_lowered();
+        let _other_gate = gate.clone();
+        lever.raise().unwrap();
+
+        assert_eq!(
+            lever.snapshot(),
+            GateSnapshot {
+                gateway: Raised,
+                epoch: 1,
+                lever_alive: true,
+                gate_count: Some(2),
+            }
+        );
+
+        lever.lower().unwrap();
+        drop(lever);
+        assert_eq!(
+            gate.snapshot(),
+            GateSnapshot {
+                gateway: Lowered,
+                epoch: 2,
+                lever_alive: false,
+                gate_count: None,
+            }
+        );
+    }
+}
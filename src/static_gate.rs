@@ -0,0 +1,93 @@
+use std::sync::OnceLock;
+
+use crate::{Gate, Gateway, Lever};
+
+/// A gate that can be declared in a `static` (usually with [`lazy_gate!`](crate::lazy_gate)),
+/// so subsystems can wait on it and control it without threading handles through every constructor
+///
+/// The lever and gate are created the first time either is used.
+/// The `static` keeps a gate of its own, so raising and lowering always works even while nobody else is waiting.
+pub struct StaticGate {
+    initial: Gateway,
+    pair: OnceLock<(Lever, Gate)>,
+}
+
+impl StaticGate {
+    /// Declare a gate that starts in the `initial` state.
+    #[must_use]
+    pub const fn new(initial: Gateway) -> Self {
+        Self {
+            initial,
+            pair: OnceLock::new(),
+        }
+    }
+
+    fn pair(&self) -> &(Lever, Gate) {
+        self.pair.get_or_init(|| crate::new(self.initial))
+    }
+
+    /// Returns a gate to wait on.
+    #[must_use]
+    pub fn gate(&self) -> Gate {
+        self.pair().1.clone()
+    }
+
+    /// Returns the lever controlling the gate.
+    #[must_use]
+    pub fn lever(&self) -> &Lever {
+        &self.pair().0
+    }
+
+    /// Returns true if the gate is raised and false if it's lowered.
+    #[must_use]
+    pub fn is_raised(&self) -> bool {
+        self.pair().1.is_raised()
+    }
+
+    /// Returns true if the gate is lowered and false if it's raised.
+    #[must_use]
+    pub fn is_lowered(&self) -> bool {
+        self.pair().1.is_lowered()
+    }
+}
+
+/// Declare a [`StaticGate`] in a `static`, starting in the given state.
+///
+/// ```
+/// use async_gate::{lazy_gate, Raised};
+///
+/// lazy_gate! {
+///     /// Whether background jobs may run
+///     pub static JOBS: Raised;
+/// }
+///
+/// JOBS.lever().lower().unwrap();
+/// assert!(JOBS.gate().is_lowered());
+/// ```
+#[macro_export]
+macro_rules! lazy_gate {
+    ($(#[$attribute:meta])* $visibility:vis static $name:ident: $initial:expr;) => {
+        $(#[$attribute])*
+        $visibility static $name: $crate::StaticGate = $crate::StaticGate::new($initial);
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Lowered;
+
+    lazy_gate!(
+        static PAUSED: Lowered;
+    );
+
+    /// Tests that a static gate starts in its initial state and is shared by everything using it.
+    #[test]
+    fn shared_through_static() {
+        assert!(PAUSED.is_lowered());
+
+        let gate = PAUSED.gate();
+        assert!(PAUSED.lever().raise().unwrap());
+        assert!(gate.is_raised());
+        assert_eq!(PAUSED.gate().current_epoch(), 1);
+    }
+}
@@ -0,0 +1,109 @@
+//! Traits for converting to and from stop signals (futures that finish when it's time to stop),
+//! so gates can plug into libraries that take "anything awaitable meaning stop"
+//! without an integration written for each one.
+//!
+//! A gate means "go" while it's raised, so as a stop signal it finishes once it's lowered.
+
+use std::future::Future;
+
+use crate::{traits::BoxFuture, Gate};
+
+/// Something that can be turned into a future that finishes when it's time to stop
+pub trait IntoStopSignal {
+    /// Turn this into a future that finishes when it's time to stop.
+    fn into_stop_signal(self) -> BoxFuture<'static, ()>;
+}
+
+/// Something that can be built to follow a stop signal
+pub trait FromStopSignal {
+    /// Build this so that it signals stopping once the `signal` finishes.
+    fn from_stop_signal(signal: impl Future<Output = ()> + Send + 'static) -> Self;
+}
+
+impl IntoStopSignal for Gate {
+    /// Returns a future that finishes once the gate is lowered (right away if it already is).
+    /// If the lever is dropped while the gate is raised, it never finishes.
+    fn into_stop_signal(self) -> BoxFuture<'static, ()> {
+        Box::pin(async move {
+            if self.lowered_owned().await.is_err() {
+                std::future::pending::<()>().await;
+            }
+        })
+    }
+}
+
+#[cfg(feature = "rt")]
+impl FromStopSignal for Gate {
+    /// Returns a gate that is raised until the `signal` finishes and lowered from then on.
+    /// Once the gate is lowered, its lever is dropped (since it will never be raised again).
+    ///
+    /// This is only available with the `rt` feature.
+    /// # Panics
+    /// Panics if called outside of a Tokio runtime.
+    fn from_stop_signal(signal: impl Future<Output = ()> + Send + 'static) -> Self {
+        crate::derived::derive(crate::Raised, move |lever| async move {
+            signal.await;
+            lever.set_gateway(crate::Lowered);
+        })
+    }
+}
+
+#[cfg(feature = "tokio-util")]
+impl IntoStopSignal for tokio_util::sync::CancellationToken {
+    /// Returns a future that finishes once the token is cancelled.
+    fn into_stop_signal(self) -> BoxFuture<'static, ()> {
+        Box::pin(async move { self.cancelled().await })
+    }
+}
+
+#[cfg(feature = "tokio-util")]
+impl FromStopSignal for tokio_util::sync::CancellationToken {
+    /// Returns a token that is cancelled once the `signal` finishes.
+    ///
+    /// This is only available with the `tokio-util` feature.
+    /// # Panics
+    /// Panics if called outside of a Tokio runtime.
+    fn from_stop_signal(signal: impl Future<Output = ()> + Send + 'static) -> Self {
+        let token = Self::new();
+        let cancel = token.clone();
+
+        tokio::spawn(async move {
+            signal.await;
+            cancel.cancel();
+        });
+
+        token
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::new_raised;
+
+    /// Tests that a gate's stop signal finishes once the gate is lowered.
+    #[test]
+    fn gate_stops_once_lowered() {
+        let (lever, gate) = new_raised();
+
+        let mut stopping = tokio_test::task::spawn(gate.into_stop_signal());
+        tokio_test::assert_pending!(stopping.poll());
+
+        lever.lower().unwrap();
+        tokio_test::assert_ready!(stopping.poll());
+    }
+
+    /// Tests that a gate from a stop signal is lowered once the signal finishes.
+    #[cfg(feature = "rt")]
+    #[tokio::test]
+    async fn gate_from_stop_signal() {
+        let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+        let mut gate = Gate::from_stop_signal(async move {
+            let _ = stopped.await;
+        });
+        assert!(gate.is_raised());
+
+        stop.send(()).unwrap();
+        gate.lowered().await.unwrap();
+    }
+}
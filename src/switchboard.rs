@@ -0,0 +1,207 @@
+//! A registry of gates looked up by name, for feature-flag style gates
+//! that are waited on in one place and flipped from somewhere (like an admin task) that only knows their names.
+
+use std::{
+    collections::BTreeMap,
+    sync::{Mutex, OnceLock},
+};
+
+use thiserror::Error;
+
+use crate::{Gate, Gateway, Lever, Lowered, Raised};
+
+/// No gate is registered under the given name
+#[derive(Debug, Error)]
+#[error("no gate is registered under that name")]
+pub struct UnknownGate;
+
+/// A registry of levers by name, handing out gates for them
+///
+/// Each switchboard is independent, but [`Switchboard::global`] gives one shared by the whole process.
+/// The switchboard keeps every lever it registered, so its gates are never told the lever was dropped
+/// (until it's [removed](Switchboard::remove) or the switchboard is dropped).
+#[derive(Default)]
+pub struct Switchboard {
+    levers: Mutex<BTreeMap<String, Lever>>,
+}
+
+impl Switchboard {
+    /// Create an empty switchboard.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the switchboard shared by the whole process.
+    #[must_use]
+    pub fn global() -> &'static Switchboard {
+        static GLOBAL: OnceLock<Switchboard> = OnceLock::new();
+
+        GLOBAL.get_or_init(Switchboard::new)
+    }
+
+    /// Returns a gate for the lever registered under `name`,
+    /// first registering a new one in the `initial` state if there isn't one yet.
+    #[must_use]
+    pub fn gate(&self, name: &str, initial: Gateway) -> Gate {
+        let mut levers = self.levers.lock().unwrap();
+
+        if let Some(lever) = levers.get(name) {
+            return lever.subscribe();
+        }
+
+        let (lever, gate) = crate::new(initial);
+        levers.insert(name.to_owned(), lever);
+        gate
+    }
+
+    /// Move the gate registered under `name` to the given `gateway`, returning the state it was in before.
+    ///
+    /// Unlike [`Lever::set`], this is fine to do while none of its gates are alive.
+    /// # Errors
+    /// If no gate is registered under `name`, an `Err` is returned.
+    pub fn set(&self, name: &str, gateway: Gateway) -> Result<Gateway, UnknownGate> {
+        let levers = self.levers.lock().unwrap();
+        let lever = levers.get(name).ok_or(UnknownGate)?;
+
+        Ok(lever.change_gateway(|current| std::mem::replace(current, gateway)))
+    }
+
+    /// Raise the gate registered under `name`, returning whether that changed its state.
+    /// # Errors
+    /// If no gate is registered under `name`, an `Err` is returned.
+    pub fn raise(&self, name: &str) -> Result<bool, UnknownGate> {
+        self.set(name, Raised).map(|previous| previous != Raised)
+    }
+
+    /// Lower the gate registered under `name`, returning whether that changed its state.
+    /// # Errors
+    /// If no gate is registered under `name`, an `Err` is returned.
+    pub fn lower(&self, name: &str) -> Result<bool, UnknownGate> {
+        self.set(name, Lowered).map(|previous| previous != Lowered)
+    }
+
+    /// Raise every registered gate, returning how many of them that changed.
+    /// This is one sweep, like with [`Switchboard::lower_where`].
+    pub fn raise_all(&self) -> usize {
+        self.set_where(|_| true, Raised)
+    }
+
+    /// Lower every registered gate, returning how many of them that changed.
+    /// This is one sweep, like with [`Switchboard::lower_where`].
+    pub fn lower_all(&self) -> usize {
+        self.set_where(|_| true, Lowered)
+    }
+
+    /// Raise every registered gate whose name `matches`, returning how many of them that changed.
+    /// This is one sweep, like with [`Switchboard::lower_where`].
+    pub fn raise_where(&self, matches: impl FnMut(&str) -> bool) -> usize {
+        self.set_where(matches, Raised)
+    }
+
+    /// Lower every registered gate whose name `matches`, returning how many of them that changed,
+    /// like `lower_where(|name| name.starts_with("nonessential/"))` during an incident.
+    ///
+    /// The whole sweep happens while holding the switchboard's lock,
+    /// so anything looking through the switchboard (like [`Switchboard::states`]) sees either none of it or all of it,
+    /// and no gate can be registered or removed partway through.
+    /// Waiters on the gates themselves are woken one gate at a time.
+    pub fn lower_where(&self, matches: impl FnMut(&str) -> bool) -> usize {
+        self.set_where(matches, Lowered)
+    }
+
+    fn set_where(&self, mut matches: impl FnMut(&str) -> bool, gateway: Gateway) -> usize {
+        let levers = self.levers.lock().unwrap();
+
+        levers
+            .iter()
+            .filter(|(name, _)| matches(name))
+            .filter(|(_, lever)| lever.set_gateway(gateway))
+            .count()
+    }
+
+    /// Returns the state of the gate registered under `name`, or `None` if there isn't one.
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<Gateway> {
+        let levers = self.levers.lock().unwrap();
+
+        levers.get(name).map(|lever| lever.sender.borrow().gateway)
+    }
+
+    /// Returns the name and current state of every registered gate, sorted by name.
+    #[must_use]
+    pub fn states(&self) -> Vec<(String, Gateway)> {
+        let levers = self.levers.lock().unwrap();
+
+        levers
+            .iter()
+            .map(|(name, lever)| (name.clone(), lever.sender.borrow().gateway))
+            .collect()
+    }
+
+    /// Forget the gate registered under `name`, dropping its lever (so waiters for the other state fail).
+    /// Returns whether there was one.
+    pub fn remove(&self, name: &str) -> bool {
+        self.levers.lock().unwrap().remove(name).is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests that gates are shared by name and flipped through the switchboard.
+    #[test]
+    fn gates_shared_by_name() {
+        let switchboard = Switchboard::new();
+        let first = switchboard.gate("maintenance", Lowered);
+        let second = switchboard.gate("maintenance", Raised);
+        assert!(second.is_lowered());
+
+        assert!(switchboard.raise("maintenance").unwrap());
+        assert!(first.is_raised() && second.is_raised());
+
+        drop((first, second));
+        assert_eq!(switchboard.set("maintenance", Lowered).unwrap(), Raised);
+        assert!(switchboard.lower("missing").is_err());
+    }
+
+    /// Tests that every registered gate is listed with its state, and removed ones aren't.
+    #[test]
+    fn lists_states() {
+        let switchboard = Switchboard::new();
+        let _beta = switchboard.gate("beta", Raised);
+        let _alpha = switchboard.gate("alpha", Lowered);
+
+        assert_eq!(
+            switchboard.states(),
+            [("alpha".to_owned(), Lowered), ("beta".to_owned(), Raised)]
+        );
+
+        assert!(switchboard.remove("beta"));
+        assert_eq!(switchboard.get("beta"), None);
+        assert_eq!(switchboard.get("alpha"), Some(Lowered));
+    }
+    /// Tests that a sweep only moves the gates it matches, and counts the ones it changed.
+    #[test]
+    fn sweeps_matching_gates() {
+        let switchboard = Switchboard::new();
+        let search = switchboard.gate("nonessential/search", Raised);
+        let thumbnails = switchboard.gate("nonessential/thumbnails", Lowered);
+        let checkout = switchboard.gate("checkout", Raised);
+
+        assert_eq!(
+            switchboard.lower_where(|name| name.starts_with("nonessential/")),
+            1
+        );
+        assert!(search.is_lowered() && thumbnails.is_lowered());
+        assert!(checkout.is_raised());
+
+        assert_eq!(switchboard.raise_all(), 2);
+        assert!(search.is_raised() && thumbnails.is_raised());
+
+        assert_eq!(switchboard.raise_where(|name| name == "checkout"), 0);
+        assert_eq!(switchboard.lower_all(), 3);
+        assert!(checkout.is_lowered());
+    }
+}
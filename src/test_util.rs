@@ -0,0 +1,318 @@
+//! Helpers for testing code driven by gates deterministically,
+//! without reinventing executor scaffolding in every crate.
+//!
+//! This module is only available with the `test-util` feature.
+
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    task::{Context, Poll, Wake, Waker},
+    time::Duration,
+};
+
+use crate::{GateDropped, Gateway, Lever};
+
+pub mod model;
+
+/// Assert that waiting for the gate to be raised is pending until `$raise` runs,
+/// and that running it wakes the waiter and lets it finish without an `Err`.
+///
+/// ```
+/// # use async_gate::assert_pending_until_raised;
+/// let (lever, mut gate) = async_gate::new_lowered();
+///
+/// assert_pending_until_raised!(gate, lever.raise().unwrap());
+/// ```
+#[macro_export]
+macro_rules! assert_pending_until_raised {
+    ($gate:expr, $raise:expr) => {{
+        let mut harness = $crate::test_util::WaitHarness::new($gate.raised());
+        assert!(harness.poll().is_pending(), "the gate was already raised");
+        $raise;
+        assert!(harness.is_woken(), "the waiter wasn't woken");
+        match harness.poll() {
+            ::std::task::Poll::Ready(Ok(())) => {}
+            ::std::task::Poll::Ready(Err(error)) => panic!("waiting failed: {error}"),
+            ::std::task::Poll::Pending => panic!("the gate still wasn't raised"),
+        }
+    }};
+}
+
+/// Assert that waiting for the gate to be lowered is pending until `$lower` runs,
+/// and that running it wakes the waiter and lets it finish without an `Err`.
+///
+/// ```
+/// # use async_gate::assert_pending_until_lowered;
+/// let (lever, mut gate) = async_gate::new_raised();
+///
+/// assert_pending_until_lowered!(gate, lever.lower().unwrap());
+/// ```
+#[macro_export]
+macro_rules! assert_pending_until_lowered {
+    ($gate:expr, $lower:expr) => {{
+        let mut harness = $crate::test_util::WaitHarness::new($gate.lowered());
+        assert!(harness.poll().is_pending(), "the gate was already lowered");
+        $lower;
+        assert!(harness.is_woken(), "the waiter wasn't woken");
+        match harness.poll() {
+            ::std::task::Poll::Ready(Ok(())) => {}
+            ::std::task::Poll::Ready(Err(error)) => panic!("waiting failed: {error}"),
+            ::std::task::Poll::Pending => panic!("the gate still wasn't lowered"),
+        }
+    }};
+}
+
+#[derive(Default)]
+struct CountingWaker {
+    wakes: AtomicUsize,
+    woken: AtomicBool,
+}
+
+impl Wake for CountingWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.wakes.fetch_add(1, Ordering::SeqCst);
+        self.woken.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Polls a future one step at a time by hand, counting how many times it was woken
+pub struct WaitHarness<F> {
+    future: Pin<Box<F>>,
+    waker: Arc<CountingWaker>,
+}
+
+impl<F: Future> WaitHarness<F> {
+    /// Wrap the given `future` without polling it yet.
+    pub fn new(future: F) -> Self {
+        Self {
+            future: Box::pin(future),
+            waker: Arc::default(),
+        }
+    }
+
+    /// Poll the future once.
+    pub fn poll(&mut self) -> Poll<F::Output> {
+        self.waker.woken.store(false, Ordering::SeqCst);
+
+        let waker = Waker::from(self.waker.clone());
+        let mut context = Context::from_waker(&waker);
+
+        self.future.as_mut().poll(&mut context)
+    }
+
+    /// Returns `true` if the future was woken since it was last polled.
+    #[must_use]
+    pub fn is_woken(&self) -> bool {
+        self.waker.woken.load(Ordering::SeqCst)
+    }
+
+    /// Returns how many times the future was woken in total.
+    #[must_use]
+    pub fn wake_count(&self) -> usize {
+        self.waker.wakes.load(Ordering::SeqCst)
+    }
+}
+
+/// A lever that plays back a script of transitions, each after a delay,
+/// using Tokio's clock (so tests can use `tokio::time::pause` to run it instantly)
+pub struct ScriptedLever {
+    lever: Lever,
+    script: Vec<(Duration, Gateway)>,
+}
+
+impl ScriptedLever {
+    /// Script the given `lever` to move to each gateway in `script`
+    /// after waiting the duration paired with it (counted from the previous transition).
+    pub fn new(lever: Lever, script: impl IntoIterator<Item = (Duration, Gateway)>) -> Self {
+        Self {
+            lever,
+            script: script.into_iter().collect(),
+        }
+    }
+
+    /// Play back the script, then give the lever back.
+    /// # Errors
+    /// If the gate is dropped before the script finishes, an `Err` is returned.
+    pub async fn run(self) -> Result<Lever, GateDropped> {
+        for (delay, gateway) in self.script {
+            tokio::time::sleep(delay).await;
+
+            self.lever.set(gateway)?;
+        }
+
+        Ok(self.lever)
+    }
+}
+
+/// A lever whose failures can be injected on command,
+/// for checking that code handles them (like [`LeverDroppedWhileLowered`](crate::LeverDroppedWhileLowered))
+/// instead of only the happy path
+pub struct FaultyLever {
+    lever: Lever,
+}
+
+impl FaultyLever {
+    /// Wrap the given `lever`.
+    #[must_use]
+    pub fn new(lever: Lever) -> Self {
+        Self { lever }
+    }
+
+    /// Returns the lever, to raise and lower the gate normally.
+    #[must_use]
+    pub fn lever(&self) -> &Lever {
+        &self.lever
+    }
+
+    /// Raise the gate only after `delay`, simulating a notification that arrives late,
+    /// returning whether it was lowered before.
+    /// # Errors
+    /// If the gate is dropped by then, an `Err` is returned.
+    pub async fn raise_after(&self, delay: Duration) -> Result<bool, GateDropped> {
+        tokio::time::sleep(delay).await;
+        self.lever.raise()
+    }
+
+    /// Lower the gate only after `delay`, simulating a notification that arrives late,
+    /// returning whether it was raised before.
+    /// # Errors
+    /// If the gate is dropped by then, an `Err` is returned.
+    pub async fn lower_after(&self, delay: Duration) -> Result<bool, GateDropped> {
+        tokio::time::sleep(delay).await;
+        self.lever.lower()
+    }
+
+    /// Drop the lever now, failing everyone waiting for the gate to leave its current state.
+    pub fn drop_lever(self) {
+        drop(self.lever);
+    }
+
+    /// Drop the lever after `delay`, failing everyone waiting for the gate to leave its state at that point.
+    pub async fn drop_lever_after(self, delay: Duration) {
+        tokio::time::sleep(delay).await;
+        self.drop_lever();
+    }
+}
+
+/// Wrap the given `future` so that the first `count` times it's pending,
+/// its task is woken again right away even though nothing happened
+/// (checking that it copes with spurious wakeups).
+pub fn spurious_wakeups<F: Future>(future: F, count: usize) -> SpuriousWakeups<F> {
+    SpuriousWakeups {
+        future: Box::pin(future),
+        remaining: count,
+    }
+}
+
+/// The future returned by [`spurious_wakeups`]
+pub struct SpuriousWakeups<F> {
+    future: Pin<Box<F>>,
+    remaining: usize,
+}
+
+impl<F: Future> Future for SpuriousWakeups<F> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let poll = self.future.as_mut().poll(cx);
+
+        if poll.is_pending() && self.remaining > 0 {
+            self.remaining -= 1;
+            cx.waker().wake_by_ref();
+        }
+
+        poll
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{new_lowered, Lowered, Raised};
+
+    /// Tests that the assertion macros pass for a gate that really is raised and lowered.
+    #[test]
+    fn assertion_macros_pass() {
+        let (lever, mut gate) = new_lowered();
+
+        assert_pending_until_raised!(gate, lever.raise().unwrap());
+        assert_pending_until_lowered!(gate, lever.lower().unwrap());
+    }
+
+    /// Tests that the assertion macro fails when the lever is dropped instead of raising the gate.
+    #[test]
+    #[should_panic(expected = "waiting failed")]
+    fn assertion_macro_fails_when_lever_dropped() {
+        let (lever, mut gate) = new_lowered();
+
+        assert_pending_until_raised!(gate, drop(lever));
+    }
+
+    /// Tests that a scripted lever applies its transitions at the scripted times.
+    #[tokio::test(start_paused = true)]
+    async fn scripted_lever_follows_script() {
+        let (lever, mut gate) = new_lowered();
+        let start = tokio::time::Instant::now();
+
+        let scripted = ScriptedLever::new(
+            lever,
+            [
+                (Duration::from_secs(1), Raised),
+                (Duration::from_secs(2), Lowered),
+            ],
+        );
+        let running = tokio::spawn(scripted.run());
+
+        gate.raised().await.unwrap();
+        assert_eq!(start.elapsed(), Duration::from_secs(1));
+
+        gate.lowered().await.unwrap();
+        assert_eq!(start.elapsed(), Duration::from_secs(3));
+
+        running.await.unwrap().unwrap();
+    }
+
+    /// Tests that a scripted lever can be stepped through with `tokio::time::advance`,
+    /// with nothing happening before each transition is due.
+    #[tokio::test]
+    async fn scripted_lever_steps_with_advance() {
+        tokio::time::pause();
+
+        let (lever, gate) = new_lowered();
+        let scripted = ScriptedLever::new(lever, [(Duration::from_secs(1), Raised)]);
+        let running = tokio::spawn(scripted.run());
+
+        tokio::time::advance(Duration::from_millis(999)).await;
+        assert!(gate.is_lowered());
+
+        tokio::time::advance(Duration::from_millis(1)).await;
+        running.await.unwrap().unwrap();
+        assert!(gate.is_raised());
+    }
+
+    /// Tests that an injected lever drop reaches a waiter that is being woken spuriously,
+    /// and that the delayed raise before it is seen.
+    #[tokio::test(start_paused = true)]
+    async fn faults_reach_waiter() {
+        let (lever, mut gate) = new_lowered();
+        let faulty = FaultyLever::new(lever);
+
+        let waiting = tokio::spawn(async move {
+            spurious_wakeups(gate.raised(), 10).await.unwrap();
+            spurious_wakeups(gate.lowered(), 10).await
+        });
+
+        faulty.raise_after(Duration::from_secs(1)).await.unwrap();
+        faulty.drop_lever_after(Duration::from_secs(1)).await;
+
+        assert!(waiting.await.unwrap().is_err());
+    }
+}
@@ -0,0 +1,264 @@
+//! A reference model of how a gate and its lever behave,
+//! and a harness that replays traces of operations against both the model and an implementation,
+//! so that the real gate (and wrappers around it) can be checked against the same semantics.
+
+use std::task::Poll;
+
+use crate::{Gate, Gateway, Lever, Lowered, Raised};
+
+use super::WaitHarness;
+
+/// Something that can be done to a gate or its lever
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Operation {
+    /// Raise the gate with the lever (nothing happens if the lever was dropped)
+    Raise,
+    /// Lower the gate with the lever (nothing happens if the lever was dropped)
+    Lower,
+    /// Drop the lever
+    DropLever,
+    /// Drop the gate
+    DropGate,
+}
+
+/// Everything that can be observed about a gate and its lever after an [`Operation`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Observation {
+    /// Whether raising or lowering succeeded (`None` if the operation wasn't one, or the lever was dropped)
+    pub transition_succeeded: Option<bool>,
+    /// The state as seen from the gate (`None` if it was dropped)
+    pub gate_state: Option<Gateway>,
+    /// The state as seen from the lever (`None` if it was dropped),
+    /// which is an `Err` if the gate was dropped
+    pub lever_state: Option<Result<Gateway, Gateway>>,
+    /// What waiting for the gate to be raised does right now (`None` if the gate was dropped):
+    /// `Ready(true)` if it finishes, `Ready(false)` if it fails, or `Pending`
+    pub raised_wait: Option<Poll<bool>>,
+    /// What waiting for the gate to be lowered does right now (`None` if the gate was dropped):
+    /// `Ready(true)` if it finishes, `Ready(false)` if it fails, or `Pending`
+    pub lowered_wait: Option<Poll<bool>>,
+}
+
+/// A gate and lever (or something built on them) that operations can be applied to
+pub trait GateSystem {
+    /// Apply the given `operation` and report what can be observed afterward.
+    fn apply(&mut self, operation: Operation) -> Observation;
+}
+
+/// The reference model of a gate and its lever
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Model {
+    gateway: Gateway,
+    lever_alive: bool,
+    gate_alive: bool,
+}
+
+impl Model {
+    /// Model a gate and lever just created in the `initial` state.
+    #[must_use]
+    pub fn new(initial: Gateway) -> Self {
+        Self {
+            gateway: initial,
+            lever_alive: true,
+            gate_alive: true,
+        }
+    }
+
+    fn wait_for(&self, target: Gateway) -> Option<Poll<bool>> {
+        self.gate_alive.then(|| {
+            if self.gateway == target {
+                Poll::Ready(true)
+            } else if self.lever_alive {
+                Poll::Pending
+            } else {
+                Poll::Ready(false)
+            }
+        })
+    }
+}
+
+impl GateSystem for Model {
+    fn apply(&mut self, operation: Operation) -> Observation {
+        let transition_succeeded = match operation {
+            Operation::Raise | Operation::Lower if self.lever_alive => {
+                if self.gate_alive {
+                    self.gateway = if operation == Operation::Raise {
+                        Raised
+                    } else {
+                        Lowered
+                    };
+                }
+                Some(self.gate_alive)
+            }
+            Operation::Raise | Operation::Lower => None,
+            Operation::DropLever => {
+                self.lever_alive = false;
+                None
+            }
+            Operation::DropGate => {
+                self.gate_alive = false;
+                None
+            }
+        };
+
+        Observation {
+            transition_succeeded,
+            gate_state: self.gate_alive.then_some(self.gateway),
+            lever_state: self.lever_alive.then_some(if self.gate_alive {
+                Ok(self.gateway)
+            } else {
+                Err(self.gateway)
+            }),
+            raised_wait: self.wait_for(Raised),
+            lowered_wait: self.wait_for(Lowered),
+        }
+    }
+}
+
+/// A real [`Lever`] and [`Gate`], applying operations for real
+pub struct RealGate {
+    lever: Option<Lever>,
+    gate: Option<Gate>,
+}
+
+impl RealGate {
+    /// Create a real gate and lever in the `initial` state.
+    #[must_use]
+    pub fn new(initial: Gateway) -> Self {
+        let (lever, gate) = crate::new(initial);
+
+        Self {
+            lever: Some(lever),
+            gate: Some(gate),
+        }
+    }
+}
+
+fn poll_once<E>(
+    harness: &mut WaitHarness<impl std::future::Future<Output = Result<(), E>>>,
+) -> Poll<bool> {
+    harness.poll().map(|result| result.is_ok())
+}
+
+impl GateSystem for RealGate {
+    fn apply(&mut self, operation: Operation) -> Observation {
+        let transition_succeeded = match operation {
+            Operation::Raise => self.lever.as_ref().map(|lever| lever.raise().is_ok()),
+            Operation::Lower => self.lever.as_ref().map(|lever| lever.lower().is_ok()),
+            Operation::DropLever => {
+                self.lever = None;
+                None
+            }
+            Operation::DropGate => {
+                self.gate = None;
+                None
+            }
+        };
+
+        let lever_state = self.lever.as_ref().map(|lever| match lever.is_raised() {
+            Ok(true) => Ok(Raised),
+            Ok(false) => Ok(Lowered),
+            Err(before_dropped) => Err(before_dropped.0),
+        });
+
+        let (gate_state, raised_wait, lowered_wait) = match &self.gate {
+            Some(gate) => {
+                let gate_state = if gate.is_raised() { Raised } else { Lowered };
+
+                let mut raised_gate = gate.clone();
+                let raised_wait = poll_once(&mut WaitHarness::new(raised_gate.raised()));
+
+                let mut lowered_gate = gate.clone();
+                let lowered_wait = poll_once(&mut WaitHarness::new(lowered_gate.lowered()));
+
+                (Some(gate_state), Some(raised_wait), Some(lowered_wait))
+            }
+            None => (None, None, None),
+        };
+
+        Observation {
+            transition_succeeded,
+            gate_state,
+            lever_state,
+            raised_wait,
+            lowered_wait,
+        }
+    }
+}
+
+/// Replay the `trace` of operations against both the reference [`Model`] (starting in the `initial` state)
+/// and the given `system`, checking that they can't be told apart.
+/// # Panics
+/// Panics at the first operation after which the `system` can be observed to differ from the model.
+pub fn replay(initial: Gateway, trace: &[Operation], system: &mut impl GateSystem) {
+    let mut model = Model::new(initial);
+
+    for (step, &operation) in trace.iter().enumerate() {
+        let expected = model.apply(operation);
+        let actual = system.apply(operation);
+
+        assert_eq!(
+            actual, expected,
+            "the system diverged from the model at step {step} ({operation:?})"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests that the real gate matches the model through toggles and a lever drop.
+    #[test]
+    fn real_gate_matches_model_through_lever_drop() {
+        let trace = [
+            Operation::Raise,
+            Operation::Raise,
+            Operation::Lower,
+            Operation::DropLever,
+            Operation::Raise,
+            Operation::DropGate,
+        ];
+
+        replay(Lowered, &trace, &mut RealGate::new(Lowered));
+    }
+
+    /// Tests that the real gate matches the model when the gate is dropped first.
+    /// (Raising a dropped gate panics in strict mode, so this only runs without it.)
+    #[cfg(not(all(feature = "strict", debug_assertions)))]
+    #[test]
+    fn real_gate_matches_model_through_gate_drop() {
+        let trace = [
+            Operation::Lower,
+            Operation::DropGate,
+            Operation::Raise,
+            Operation::DropLever,
+        ];
+
+        replay(Raised, &trace, &mut RealGate::new(Raised));
+    }
+
+    /// A gate that forgets to fail waits when its lever drops
+    struct Forgetful(Model);
+
+    impl GateSystem for Forgetful {
+        fn apply(&mut self, operation: Operation) -> Observation {
+            let mut observation = self.0.apply(operation);
+            if observation.raised_wait == Some(Poll::Ready(false)) {
+                observation.raised_wait = Some(Poll::Pending);
+            }
+            observation
+        }
+    }
+
+    /// Tests that replaying catches a system that diverges from the model.
+    #[test]
+    #[should_panic(expected = "diverged from the model at step 0 (DropLever)")]
+    fn replay_catches_divergence() {
+        replay(
+            Lowered,
+            &[Operation::DropLever],
+            &mut Forgetful(Model::new(Lowered)),
+        );
+    }
+}
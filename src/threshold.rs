@@ -0,0 +1,160 @@
+//! Gates driven by an analog value (like the fraction of free memory) with hysteresis:
+//! the gate is raised once the value goes above one threshold
+//! and only lowered again once it goes below a second, lower threshold,
+//! so a value hovering around a single threshold can't make the gate flap.
+
+use crate::{Gate, GateDropped, Gateway, Lever, Lowered, Raised};
+
+/// A lever that raises and lowers its gate according to samples of an analog value
+pub struct ThresholdLever {
+    lever: Lever,
+    raise_above: f64,
+    lower_below: f64,
+}
+
+impl ThresholdLever {
+    /// Drive the given `lever` by samples: raising the gate when a sample is above `raise_above`
+    /// and lowering it when a sample is below `lower_below`.
+    /// # Panics
+    /// Panics if `lower_below` is greater than `raise_above` (so there would be no hysteresis).
+    #[must_use]
+    pub fn new(lever: Lever, raise_above: f64, lower_below: f64) -> Self {
+        assert!(
+            lower_below <= raise_above,
+            "the lowering threshold must not be above the raising threshold"
+        );
+
+        Self {
+            lever,
+            raise_above,
+            lower_below,
+        }
+    }
+
+    /// Returns the state the given `sample` moves a gate in the `current` state to.
+    fn next(&self, current: Gateway, sample: f64) -> Gateway {
+        if sample > self.raise_above {
+            Raised
+        } else if sample < self.lower_below {
+            Lowered
+        } else {
+            // Between the thresholds (or NaN), nothing changes
+            current
+        }
+    }
+
+    /// Feed in a new `sample` of the value, raising or lowering the gate if it crossed a threshold.
+    /// Returns whether that changed the gate's state.
+    /// # Errors
+    /// If the gate was dropped, an `Err` is returned.
+    pub fn push(&self, sample: f64) -> Result<bool, GateDropped> {
+        let current = self.lever.sender.borrow().gateway;
+
+        match self.next(current, sample) {
+            Raised => self.lever.raise(),
+            Lowered => self.lever.lower(),
+        }
+    }
+
+    /// Returns the lever being driven.
+    #[must_use]
+    pub fn lever(&self) -> &Lever {
+        &self.lever
+    }
+}
+
+/// Create a gate driven by samples pushed into the returned [`ThresholdLever`]:
+/// it is raised when a sample is above `raise_above` and lowered when a sample is below `lower_below`.
+/// It starts in the `initial` state.
+/// # Panics
+/// Panics if `lower_below` is greater than `raise_above`.
+#[must_use]
+pub fn new(initial: Gateway, raise_above: f64, lower_below: f64) -> (ThresholdLever, Gate) {
+    let (lever, gate) = crate::new(initial);
+
+    (ThresholdLever::new(lever, raise_above, lower_below), gate)
+}
+
+/// Create a gate driven by the values sent through the given `receiver`:
+/// it is raised when the value is above `raise_above` and lowered when it is below `lower_below`.
+/// It starts raised if the current value is above `raise_above` and lowered otherwise.
+/// Its lever is dropped once the `receiver`'s sender is dropped.
+///
+/// This is only available with the `rt` feature.
+/// # Panics
+/// Panics if `lower_below` is greater than `raise_above` or if called outside of a Tokio runtime.
+#[cfg(feature = "rt")]
+#[must_use]
+pub fn from_watch(
+    mut receiver: tokio::sync::watch::Receiver<f64>,
+    raise_above: f64,
+    lower_below: f64,
+) -> Gate {
+    assert!(
+        lower_below <= raise_above,
+        "the lowering threshold must not be above the raising threshold"
+    );
+
+    let initial = if *receiver.borrow_and_update() > raise_above {
+        Raised
+    } else {
+        Lowered
+    };
+
+    crate::derived::derive(initial, move |lever| async move {
+        let threshold = ThresholdLever::new(lever, raise_above, lower_below);
+
+        while receiver.changed().await.is_ok() {
+            let sample = *receiver.borrow_and_update();
+            let current = threshold.lever.sender.borrow().gateway;
+            // Not going through `push` so that a gate dropped in the meantime doesn't count as misuse
+            threshold.lever.set_gateway(threshold.next(current, sample));
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests that values between the thresholds leave the gate as it is
+    /// and only crossing a threshold changes it.
+    #[test]
+    fn applies_hysteresis() {
+        let (lever, gate) = new(Lowered, 0.2, 0.1);
+
+        lever.push(0.15).unwrap();
+        assert!(gate.is_lowered());
+
+        lever.push(0.25).unwrap();
+        assert!(gate.is_raised());
+
+        lever.push(0.15).unwrap();
+        assert!(gate.is_raised());
+
+        lever.push(f64::NAN).unwrap();
+        assert!(gate.is_raised());
+
+        lever.push(0.05).unwrap();
+        assert!(gate.is_lowered());
+    }
+
+    /// Tests that a gate can follow a watch channel of values.
+    #[cfg(feature = "rt")]
+    #[tokio::test]
+    async fn follows_watch_channel() {
+        let (sender, receiver) = tokio::sync::watch::channel(0.5);
+        let mut gate = from_watch(receiver, 0.2, 0.1);
+        assert!(gate.is_raised());
+
+        sender.send(0.15).unwrap();
+        tokio::task::yield_now().await;
+        assert!(gate.is_raised());
+
+        sender.send(0.05).unwrap();
+        gate.lowered().await.unwrap();
+
+        drop(sender);
+        assert!(gate.raised().await.is_err());
+    }
+}
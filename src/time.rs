@@ -0,0 +1,561 @@
+//! Waiting with a time limit and throttling wakeups, on Tokio's clock
+//! (so tests can use `tokio::time::pause` and `advance` to control it).
+//!
+//! This module is only available with the `time` feature.
+
+use std::{future::Future, sync::Arc, time::Duration};
+
+use thiserror::Error;
+use tokio::{runtime::Handle, time::Instant};
+
+use crate::{
+    derived, Gate, GateBuilder, Gateway, LeverDroppedWhileLowered, LeverDroppedWhileRaised,
+    LeverShared, State,
+};
+
+/// Waiting for the gate to be raised didn't succeed
+#[derive(Debug, Error)]
+pub enum RaisedTimeoutError {
+    /// The lever was dropped while the gate was lowered, so it will never be raised
+    #[error(transparent)]
+    LeverDroppedWhileLowered(#[from] LeverDroppedWhileLowered),
+    /// The gate wasn't raised in time
+    #[error("timed out waiting for the gate to be raised")]
+    TimedOut,
+}
+
+/// Waiting for the gate to be lowered didn't succeed
+#[derive(Debug, Error)]
+pub enum LoweredTimeoutError {
+    /// The lever was dropped while the gate was raised, so it will never be lowered
+    #[error(transparent)]
+    LeverDroppedWhileRaised(#[from] LeverDroppedWhileRaised),
+    /// The gate wasn't lowered in time
+    #[error("timed out waiting for the gate to be lowered")]
+    TimedOut,
+}
+
+impl GateBuilder {
+    /// Set how long [`Gate::timed_raised`] and [`Gate::timed_lowered`] wait before giving up,
+    /// for every gate of this channel (by default, they wait forever like [`Gate::raised`] and [`Gate::lowered`]).
+    ///
+    /// This only applies to those two methods: every other wait (like [`Gate::raised`]) still waits for as long as it takes.
+    pub fn timed_wait_timeout(mut self, timeout: Duration) -> Self {
+        self.state.timed_wait_timeout = Some(timeout);
+        self
+    }
+
+    /// Limit how often the lever wakes waiting tasks to once per `cooldown`,
+    /// so that a rapidly flapping lever can't cause storms of wakeups.
+    ///
+    /// Transitions still happen right away (so [`Gate::is_raised`] and [`Gate::is_lowered`] see them),
+    /// but waiters are only woken once the cooldown since the last wakeup is over,
+    /// when they see whatever state the gate is in by then.
+    /// Delivering those late wakeups needs a Tokio runtime to be running when the lever is used;
+    /// without one, waiters are woken right away.
+    pub fn wake_cooldown(mut self, cooldown: Duration) -> Self {
+        self.state.wake_throttle = Some(WakeThrottle {
+            cooldown,
+            next_wake: None,
+            wake_scheduled: false,
+        });
+        self
+    }
+}
+
+/// Wait for `wait`, calling `progress` with the time waited so far every `interval` until it finishes.
+async fn with_progress<T>(
+    wait: impl Future<Output = T>,
+    interval: Duration,
+    mut progress: impl FnMut(Duration),
+) -> T {
+    let start = Instant::now();
+    let mut ticks = tokio::time::interval_at(start + interval, interval);
+    let mut wait = std::pin::pin!(wait);
+
+    loop {
+        tokio::select! {
+            biased;
+            result = &mut wait => return result,
+            _ = ticks.tick() => progress(start.elapsed()),
+        }
+    }
+}
+
+/// Bookkeeping for limiting how often a lever wakes waiters
+#[derive(Debug, Clone)]
+pub(crate) struct WakeThrottle {
+    cooldown: Duration,
+    /// The earliest time waiters may be woken again
+    next_wake: Option<Instant>,
+    /// Whether a task is already waiting to wake waiters once `next_wake` comes
+    wake_scheduled: bool,
+}
+
+impl WakeThrottle {
+    /// Forget when waiters were last woken.
+    pub(crate) fn reset(&mut self) {
+        self.next_wake = None;
+    }
+}
+
+/// Apply `change` to the gate's state (which returns whether it changed anything),
+/// but only wake waiters if the last wakeup was at least the cooldown ago.
+/// Otherwise, the new state is recorded right away (for anyone checking it),
+/// and waiters are woken when the cooldown is over.
+pub(crate) fn change_gateway_throttled(
+    sender: &Arc<LeverShared>,
+    change: impl FnOnce(&mut State) -> bool,
+) {
+    let mut wake_at = None;
+
+    sender.send_if_modified(|state| {
+        if !change(state) {
+            return false;
+        }
+
+        let Some(throttle) = &mut state.wake_throttle else {
+            return true;
+        };
+
+        let now = Instant::now();
+        match throttle.next_wake {
+            Some(next_wake) if now < next_wake => {
+                if !throttle.wake_scheduled {
+                    throttle.wake_scheduled = true;
+                    wake_at = Some(next_wake);
+                }
+                false
+            }
+            _ => {
+                throttle.next_wake = Some(now + throttle.cooldown);
+                true
+            }
+        }
+    });
+
+    let Some(wake_at) = wake_at else {
+        return;
+    };
+
+    let wake = |sender: &LeverShared| {
+        sender.send_modify(|state| {
+            if let Some(throttle) = &mut state.wake_throttle {
+                throttle.wake_scheduled = false;
+                throttle.next_wake = Some(Instant::now() + throttle.cooldown);
+            }
+        });
+    };
+
+    if let Ok(runtime) = Handle::try_current() {
+        // If the lever is dropped in the meantime, dropping it already woke everyone
+        let sender = Arc::downgrade(sender);
+
+        runtime.spawn(async move {
+            tokio::time::sleep_until(wake_at).await;
+
+            if let Some(sender) = sender.upgrade() {
+                wake(&sender);
+            }
+        });
+    } else {
+        // Without a runtime to wake waiters later, wake them now rather than never
+        wake(sender);
+    }
+}
+
+/// Wait until the `source` gate is in the given `gateway`, returning `false` if its lever was dropped before that
+async fn reached(source: &mut Gate, gateway: Gateway) -> bool {
+    source
+        .receiver
+        .wait_for(|state| state.gateway == gateway)
+        .await
+        .is_ok()
+}
+
+impl Gate {
+    /// Returns a new gate following this one, but only once this one has stayed in a new state for `duration`:
+    /// changes that are undone within `duration` are never passed on.
+    /// This suppresses flapping for consumers that must not flicker, like UIs and alerts.
+    ///
+    /// The new gate starts in this gate's current state.
+    /// Its lever is dropped once this gate's lever is dropped (after passing on the final state if it lasts).
+    /// # Panics
+    /// Panics if called outside of a Tokio runtime.
+    #[must_use]
+    pub fn debounced(&self, duration: Duration) -> Gate {
+        let mut source = self.clone();
+        let initial = source.receiver.borrow_and_update().gateway;
+
+        derived::derive(initial, move |lever| async move {
+            let mut current = initial;
+
+            loop {
+                let target = !current;
+                if !reached(&mut source, target).await {
+                    return;
+                }
+
+                let deadline = Instant::now() + duration;
+                tokio::select! {
+                    () = tokio::time::sleep_until(deadline) => {
+                        lever.set_gateway(target);
+                        current = target;
+                    }
+                    reverted = reached(&mut source, current) => {
+                        if !reverted {
+                            // The source lever was dropped in the target state, so the change lasts
+                            tokio::time::sleep_until(deadline).await;
+                            lever.set_gateway(target);
+                            return;
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    /// Returns a new gate following this one, but changing at most once per `period`:
+    /// changes of this gate during a period are held back,
+    /// and the state this gate is in once the period is over is passed on then.
+    /// This protects consumers from a source that flaps rapidly.
+    ///
+    /// The new gate starts in this gate's current state.
+    /// Its lever is dropped once this gate's lever is dropped (after passing on the final state).
+    /// # Panics
+    /// Panics if called outside of a Tokio runtime.
+    #[must_use]
+    pub fn throttled(&self, period: Duration) -> Gate {
+        let mut source = self.clone();
+        let initial = source.receiver.borrow_and_update().gateway;
+
+        derived::derive(initial, move |lever| async move {
+            let mut current = initial;
+
+            loop {
+                let lever_dropped = !reached(&mut source, !current).await;
+                let latest = source.receiver.borrow().gateway;
+
+                if latest != current {
+                    lever.set_gateway(latest);
+                    current = latest;
+                }
+
+                if lever_dropped {
+                    return;
+                }
+
+                tokio::time::sleep(period).await;
+            }
+        })
+    }
+
+    /// Wait until the gate is raised, like [`Gate::raised`],
+    /// but call `progress` with how long it's been waiting every `interval` until then,
+    /// so long waits can be logged instead of being silent.
+    /// # Errors
+    /// If the lever is dropped while the gate is lowered, an `Err` is returned.
+    /// # Panics
+    /// Panics if `interval` is zero.
+    /// # Cancel safety
+    /// This method is cancel safe, for the same reasons as [`Gate::raised`].
+    pub async fn raised_with_progress(
+        &mut self,
+        interval: Duration,
+        progress: impl FnMut(Duration),
+    ) -> Result<(), LeverDroppedWhileLowered> {
+        with_progress(self.raised(), interval, progress).await
+    }
+
+    /// Wait until the gate is lowered, like [`Gate::lowered`],
+    /// but call `progress` with how long it's been waiting every `interval` until then,
+    /// so long waits can be logged instead of being silent.
+    /// # Errors
+    /// If the lever is dropped while the gate is raised, an `Err` is returned.
+    /// # Panics
+    /// Panics if `interval` is zero.
+    /// # Cancel safety
+    /// This method is cancel safe, for the same reasons as [`Gate::lowered`].
+    pub async fn lowered_with_progress(
+        &mut self,
+        interval: Duration,
+        progress: impl FnMut(Duration),
+    ) -> Result<(), LeverDroppedWhileRaised> {
+        with_progress(self.lowered(), interval, progress).await
+    }
+
+    /// Returns how long [`Gate::timed_raised`] and [`Gate::timed_lowered`] wait before giving up,
+    /// as configured with [`GateBuilder::timed_wait_timeout`] (`None` if they wait forever).
+    #[must_use]
+    pub fn timed_wait_timeout(&self) -> Option<Duration> {
+        self.receiver.borrow().timed_wait_timeout
+    }
+
+    /// Wait until the gate is raised, like [`Gate::raised`],
+    /// but give up after the [timeout](GateBuilder::timed_wait_timeout) configured for this gate.
+    /// # Errors
+    /// If the lever is dropped while the gate is lowered, an `Err(RaisedTimeoutError::LeverDroppedWhileLowered)` is returned.
+    /// If the gate isn't raised before the timeout, an `Err(RaisedTimeoutError::TimedOut)` is returned.
+    /// # Cancel safety
+    /// This method is cancel safe, for the same reasons as [`Gate::raised`].
+    pub async fn timed_raised(&mut self) -> Result<(), RaisedTimeoutError> {
+        match self.timed_wait_timeout() {
+            Some(timeout) => self.raised_timeout(timeout).await,
+            None => Ok(self.raised().await?),
+        }
+    }
+
+    /// Wait until the gate is lowered, like [`Gate::lowered`],
+    /// but give up after the [timeout](GateBuilder::timed_wait_timeout) configured for this gate.
+    /// # Errors
+    /// If the lever is dropped while the gate is raised, an `Err(LoweredTimeoutError::LeverDroppedWhileRaised)` is returned.
+    /// If the gate isn't lowered before the timeout, an `Err(LoweredTimeoutError::TimedOut)` is returned.
+    /// # Cancel safety
+    /// This method is cancel safe, for the same reasons as [`Gate::lowered`].
+    pub async fn timed_lowered(&mut self) -> Result<(), LoweredTimeoutError> {
+        match self.timed_wait_timeout() {
+            Some(timeout) => self.lowered_timeout(timeout).await,
+            None => Ok(self.lowered().await?),
+        }
+    }
+
+    /// Wait until the gate is raised, like [`Gate::raised`], but give up after `timeout`.
+    /// # Errors
+    /// If the lever is dropped while the gate is lowered, an `Err(RaisedTimeoutError::LeverDroppedWhileLowered)` is returned.
+    /// If the gate isn't raised before the timeout, an `Err(RaisedTimeoutError::TimedOut)` is returned.
+    /// # Cancel safety
+    /// This method is cancel safe, for the same reasons as [`Gate::raised`].
+    pub async fn raised_timeout(&mut self, timeout: Duration) -> Result<(), RaisedTimeoutError> {
+        tokio::time::timeout(timeout, self.raised())
+            .await
+            .map_err(|_| RaisedTimeoutError::TimedOut)?
+            .map_err(Into::into)
+    }
+
+    /// Wait until the gate is raised, like [`Gate::raised`], but give up once the `deadline` passes.
+    /// # Errors
+    /// If the lever is dropped while the gate is lowered, an `Err(RaisedTimeoutError::LeverDroppedWhileLowered)` is returned.
+    /// If the gate isn't raised before the deadline, an `Err(RaisedTimeoutError::TimedOut)` is returned.
+    /// # Cancel safety
+    /// This method is cancel safe, for the same reasons as [`Gate::raised`].
+    pub async fn raised_before(&mut self, deadline: Instant) -> Result<(), RaisedTimeoutError> {
+        tokio::time::timeout_at(deadline, self.raised())
+            .await
+            .map_err(|_| RaisedTimeoutError::TimedOut)?
+            .map_err(Into::into)
+    }
+
+    /// Wait until the gate is lowered, like [`Gate::lowered`], but give up after `timeout`.
+    /// # Errors
+    /// If the lever is dropped while the gate is raised, an `Err(LoweredTimeoutError::LeverDroppedWhileRaised)` is returned.
+    /// If the gate isn't lowered before the timeout, an `Err(LoweredTimeoutError::TimedOut)` is returned.
+    /// # Cancel safety
+    /// This method is cancel safe, for the same reasons as [`Gate::lowered`].
+    pub async fn lowered_timeout(&mut self, timeout: Duration) -> Result<(), LoweredTimeoutError> {
+        tokio::time::timeout(timeout, self.lowered())
+            .await
+            .map_err(|_| LoweredTimeoutError::TimedOut)?
+            .map_err(Into::into)
+    }
+
+    /// Wait until the gate is lowered, like [`Gate::lowered`], but give up once the `deadline` passes.
+    /// # Errors
+    /// If the lever is dropped while the gate is raised, an `Err(LoweredTimeoutError::LeverDroppedWhileRaised)` is returned.
+    /// If the gate isn't lowered before the deadline, an `Err(LoweredTimeoutError::TimedOut)` is returned.
+    /// # Cancel safety
+    /// This method is cancel safe, for the same reasons as [`Gate::lowered`].
+    pub async fn lowered_before(&mut self, deadline: Instant) -> Result<(), LoweredTimeoutError> {
+        tokio::time::timeout_at(deadline, self.lowered())
+            .await
+            .map_err(|_| LoweredTimeoutError::TimedOut)?
+            .map_err(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Raised;
+
+    /// Tests that every gate of a channel built with a timed wait timeout gives up after it.
+    #[tokio::test(start_paused = true)]
+    async fn timed_wait_timeout_applies_to_clones() {
+        let (_lever, gate) = Gate::builder()
+            .timed_wait_timeout(Duration::from_secs(5))
+            .build();
+        let mut clone = gate.clone();
+
+        let start = tokio::time::Instant::now();
+        assert!(matches!(
+            clone.timed_raised().await,
+            Err(RaisedTimeoutError::TimedOut)
+        ));
+        assert_eq!(start.elapsed(), Duration::from_secs(5));
+    }
+
+    /// Tests that timed waits without a timeout still finish (or fail) like normal.
+    #[tokio::test(start_paused = true)]
+    async fn no_timed_wait_timeout_waits_normally() {
+        let (lever, mut gate) = Gate::builder().initially(Raised).build();
+        assert_eq!(gate.timed_wait_timeout(), None);
+
+        gate.timed_raised().await.unwrap();
+
+        drop(lever);
+        assert!(matches!(
+            gate.timed_lowered().await,
+            Err(LoweredTimeoutError::LeverDroppedWhileRaised(_))
+        ));
+    }
+
+    /// Tests that with a wake cooldown, transitions are visible right away
+    /// but a parked waiter is only woken once the cooldown since the last wakeup is over.
+    #[tokio::test(start_paused = true)]
+    async fn wake_cooldown_delays_wakeups() {
+        let (lever, gate) = Gate::builder()
+            .wake_cooldown(Duration::from_secs(1))
+            .build();
+        let start = Instant::now();
+
+        lever.raise().unwrap();
+
+        let mut waiting_gate = gate.clone();
+        let waiting = tokio::spawn(async move {
+            waiting_gate.lowered().await.unwrap();
+            start.elapsed()
+        });
+        tokio::task::yield_now().await;
+
+        lever.lower().unwrap();
+        assert!(gate.is_lowered());
+
+        assert_eq!(waiting.await.unwrap(), Duration::from_secs(1));
+    }
+
+    /// Tests that a burst of transitions during the cooldown results in one late wakeup
+    /// that sees the final state.
+    #[tokio::test(start_paused = true)]
+    async fn wake_cooldown_coalesces_burst() {
+        let (lever, gate) = Gate::builder()
+            .initially(Raised)
+            .wake_cooldown(Duration::from_secs(1))
+            .build();
+        let start = Instant::now();
+
+        lever.lower().unwrap();
+
+        let mut waiting_gate = gate.clone();
+        let waiting = tokio::spawn(async move {
+            waiting_gate.raised().await.unwrap();
+            (start.elapsed(), waiting_gate.is_raised())
+        });
+        tokio::task::yield_now().await;
+
+        for _ in 0..10 {
+            lever.raise().unwrap();
+            lever.lower().unwrap();
+        }
+        lever.raise().unwrap();
+
+        assert_eq!(waiting.await.unwrap(), (Duration::from_secs(1), true));
+        assert!(gate.is_raised());
+    }
+
+    /// Tests that a debounced gate ignores a flap shorter than its duration
+    /// and passes on a change that lasts.
+    #[tokio::test(start_paused = true)]
+    async fn debounced_ignores_short_flaps() {
+        let (lever, gate) = crate::new_lowered();
+        let mut debounced = gate.debounced(Duration::from_secs(1));
+        let start = Instant::now();
+
+        lever.raise().unwrap();
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        lever.lower().unwrap();
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        assert!(debounced.is_lowered());
+
+        lever.raise().unwrap();
+        debounced.raised().await.unwrap();
+        assert_eq!(start.elapsed(), Duration::from_secs(2));
+    }
+
+    /// Tests that a throttled gate passes on the first change right away,
+    /// then holds back changes until the period is over and passes on the latest state.
+    #[tokio::test(start_paused = true)]
+    async fn throttled_keeps_latest_state() {
+        let (lever, gate) = crate::new_lowered();
+        let mut throttled = gate.throttled(Duration::from_secs(1));
+        let start = Instant::now();
+
+        lever.raise().unwrap();
+        throttled.raised().await.unwrap();
+        assert_eq!(start.elapsed(), Duration::ZERO);
+
+        lever.lower().unwrap();
+        lever.raise().unwrap();
+        lever.lower().unwrap();
+
+        throttled.lowered().await.unwrap();
+        assert_eq!(start.elapsed(), Duration::from_secs(1));
+
+        drop(lever);
+        assert!(throttled.raised().await.is_err());
+    }
+
+    /// Tests that progress is reported every interval until the gate is raised.
+    #[tokio::test(start_paused = true)]
+    async fn reports_progress_while_waiting() {
+        let (lever, mut gate) = crate::new_lowered();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_secs(70)).await;
+            lever.raise()
+        });
+
+        let mut reports = Vec::new();
+        gate.raised_with_progress(Duration::from_secs(30), |elapsed| reports.push(elapsed))
+            .await
+            .unwrap();
+
+        assert_eq!(reports, [Duration::from_secs(30), Duration::from_secs(60)]);
+    }
+
+    /// Tests that waiting with a timeout tells timing out apart from the lever being dropped.
+    #[tokio::test(start_paused = true)]
+    async fn timeout_distinguishes_errors() {
+        let (lever, mut gate) = crate::new_lowered();
+
+        assert!(matches!(
+            gate.raised_timeout(Duration::from_secs(1)).await,
+            Err(RaisedTimeoutError::TimedOut)
+        ));
+        gate.lowered_timeout(Duration::from_secs(1)).await.unwrap();
+
+        drop(lever);
+        assert!(matches!(
+            gate.raised_timeout(Duration::from_secs(1)).await,
+            Err(RaisedTimeoutError::LeverDroppedWhileLowered(_))
+        ));
+    }
+
+    /// Tests that several waits can share one deadline.
+    #[tokio::test(start_paused = true)]
+    async fn waits_share_deadline() {
+        let (lever, mut gate) = crate::new_lowered();
+        let deadline = Instant::now() + Duration::from_secs(3);
+
+        let raising = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_secs(2)).await;
+            lever.raise().unwrap();
+            lever
+        });
+
+        gate.raised_before(deadline).await.unwrap();
+        let _lever = raising.await.unwrap();
+        assert!(matches!(
+            gate.lowered_before(deadline).await,
+            Err(LoweredTimeoutError::TimedOut)
+        ));
+        assert_eq!(Instant::now(), deadline);
+    }
+}
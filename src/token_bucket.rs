@@ -0,0 +1,153 @@
+//! A rate limiter shaped like a gate:
+//! the gate is raised while the bucket has tokens and lowered while it's empty,
+//! so rate limiting composes with everything else that works with gates.
+//!
+//! This module is only available with the `time` feature.
+
+use std::{
+    sync::{Arc, Mutex, Weak},
+    time::Duration,
+};
+
+use crate::{new_raised, Gate, Lever};
+
+struct Bucket {
+    lever: Lever,
+    tokens: Mutex<u32>,
+    capacity: u32,
+}
+
+/// A gate that is raised while its token bucket has tokens,
+/// where each [`passage`](TokenGate::passage) takes one token
+///
+/// Cloning a `TokenGate` shares the same bucket.
+#[derive(Clone)]
+pub struct TokenGate {
+    gate: Gate,
+    bucket: Arc<Bucket>,
+}
+
+/// Create a full token bucket holding up to `capacity` tokens, with a background task adding one token every `refill_every`.
+/// The task stops once every clone of the returned [`TokenGate`] is dropped.
+/// # Panics
+/// Panics if `capacity` is 0, if `refill_every` is zero (the bucket would never run dry),
+/// or if called outside of a Tokio runtime.
+#[must_use]
+pub fn new(capacity: u32, refill_every: Duration) -> TokenGate {
+    assert!(
+        capacity > 0,
+        "a token bucket must be able to hold some tokens"
+    );
+    assert!(
+        !refill_every.is_zero(),
+        "a token bucket must take some time to refill"
+    );
+
+    let (lever, gate) = new_raised();
+    let bucket = Arc::new(Bucket {
+        lever,
+        tokens: Mutex::new(capacity),
+        capacity,
+    });
+
+    tokio::spawn(refill(Arc::downgrade(&bucket), refill_every));
+
+    TokenGate { gate, bucket }
+}
+
+async fn refill(bucket: Weak<Bucket>, refill_every: Duration) {
+    let start = tokio::time::Instant::now() + refill_every;
+    let mut interval = tokio::time::interval_at(start, refill_every);
+
+    loop {
+        interval.tick().await;
+
+        let Some(bucket) = bucket.upgrade() else {
+            return;
+        };
+
+        let mut tokens = bucket.tokens.lock().unwrap();
+        if *tokens < bucket.capacity {
+            *tokens += 1;
+            let _ = bucket.lever.raise();
+        }
+    }
+}
+
+impl TokenGate {
+    /// Wait until a token is available, then take it.
+    /// # Cancel safety
+    /// This method is cancel safe: a token is only taken right before it returns.
+    pub async fn passage(&mut self) {
+        loop {
+            // The bucket holds the lever, so it can't be dropped while we're waiting
+            let _ = self.gate.raised().await;
+
+            if self.try_passage() {
+                return;
+            }
+        }
+    }
+
+    /// Take a token if one is available right now, returning `false` if the bucket is empty.
+    pub fn try_passage(&self) -> bool {
+        let mut tokens = self.bucket.tokens.lock().unwrap();
+
+        if *tokens == 0 {
+            return false;
+        }
+
+        *tokens -= 1;
+        if *tokens == 0 {
+            let _ = self.bucket.lever.lower();
+        }
+
+        true
+    }
+
+    /// Returns how many tokens are in the bucket right now.
+    #[must_use]
+    pub fn tokens(&self) -> u32 {
+        *self.bucket.tokens.lock().unwrap()
+    }
+
+    /// Returns the gate that is raised while the bucket has tokens,
+    /// for use with everything that works with gates.
+    #[must_use]
+    pub fn gate(&self) -> &Gate {
+        &self.gate
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests that taking every token lowers the gate,
+    /// and the refill raises it again one token at a time.
+    #[tokio::test(start_paused = true)]
+    async fn lowers_when_empty_and_refills() {
+        let mut bucket = new(2, Duration::from_secs(1));
+        let start = tokio::time::Instant::now();
+
+        bucket.passage().await;
+        assert!(bucket.gate().is_raised());
+        bucket.passage().await;
+        assert!(bucket.gate().is_lowered());
+        assert!(!bucket.try_passage());
+
+        bucket.passage().await;
+        assert_eq!(start.elapsed(), Duration::from_secs(1));
+        assert_eq!(bucket.tokens(), 0);
+
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        assert_eq!(bucket.tokens(), 2);
+    }
+
+    /// Tests that a bucket can't be made to refill instantly.
+    #[test]
+    #[should_panic = "some time to refill"]
+    fn rejects_zero_refill() {
+        let _ = new(1, Duration::ZERO);
+    }
+}
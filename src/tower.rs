@@ -0,0 +1,130 @@
+//! Gates following whether a [`Service`] is ready,
+//! so the rest of an application can wait on (or check) a service's readiness.
+//!
+//! This module is only available with the `tower` feature.
+
+use std::task::{Context, Poll};
+
+use tower_service::Service;
+
+use crate::{Gate, Gateway, Lever, Lowered, Raised};
+
+/// A service that moves a gate to follow its inner service's readiness whenever it's polled:
+/// raising it when [`poll_ready`](Service::poll_ready) is ready
+/// and lowering it when it's pending or fails
+///
+/// The gate reflects the most recent call to `poll_ready`,
+/// so it only changes while something is driving this service.
+pub struct ReadinessLever<S> {
+    inner: S,
+    lever: Lever,
+}
+
+impl<S> ReadinessLever<S> {
+    /// Returns the lever being driven.
+    #[must_use]
+    pub fn lever(&self) -> &Lever {
+        &self.lever
+    }
+
+    /// Returns the inner service.
+    #[must_use]
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Stop following the inner service's readiness, and get it back.
+    #[must_use]
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S: Service<Request>, Request> Service<Request> for ReadinessLever<S> {
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let poll = self.inner.poll_ready(cx);
+
+        // Nobody watching the gate anymore doesn't stop the service from working,
+        // so this doesn't count as misusing a lever whose gate was dropped
+        self.lever.set_gateway(match poll {
+            Poll::Ready(Ok(())) => Raised,
+            Poll::Ready(Err(_)) | Poll::Pending => Lowered,
+        });
+
+        poll
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        self.inner.call(request)
+    }
+}
+
+/// Wrap the given `service` so that the returned gate follows its readiness (see [`ReadinessLever`]).
+/// The gate starts in the `initial` state, until the service is first polled.
+#[must_use]
+pub fn readiness<S>(service: S, initial: Gateway) -> (ReadinessLever<S>, Gate) {
+    let (lever, gate) = crate::new(initial);
+
+    (
+        ReadinessLever {
+            inner: service,
+            lever,
+        },
+        gate,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        convert::Infallible,
+        future::{ready, Ready},
+    };
+
+    use tokio_test::task;
+
+    use super::*;
+
+    /// A service that's ready whenever the flag is set
+    struct Toggle(bool);
+
+    impl Service<()> for Toggle {
+        type Response = ();
+        type Error = Infallible;
+        type Future = Ready<Result<(), Infallible>>;
+
+        fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+            if self.0 {
+                Poll::Ready(Ok(()))
+            } else {
+                Poll::Pending
+            }
+        }
+
+        fn call(&mut self, (): ()) -> Self::Future {
+            ready(Ok(()))
+        }
+    }
+
+    /// Tests that the gate follows the readiness seen by each poll.
+    #[test]
+    fn follows_poll_ready() {
+        let (mut service, gate) = readiness(Toggle(false), Lowered);
+        let mut polling = task::spawn(());
+
+        assert!(polling.enter(|cx, _| service.poll_ready(cx)).is_pending());
+        assert!(gate.is_lowered());
+
+        service.inner.0 = true;
+        assert!(polling.enter(|cx, _| service.poll_ready(cx)).is_ready());
+        assert!(gate.is_raised());
+
+        service.inner.0 = false;
+        assert!(polling.enter(|cx, _| service.poll_ready(cx)).is_pending());
+        assert!(gate.is_lowered());
+    }
+}
@@ -0,0 +1,112 @@
+//! Showing when work was gated off in traces.
+//!
+//! This module is only available with the `tracing` feature.
+
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use pin_project_lite::pin_project;
+use tracing::Span;
+
+use crate::Gate;
+
+pin_project! {
+    /// A future that enters its span while it's polled only if its gate is raised at the time
+    /// (see [`Gate::entered_span`])
+    #[must_use = "futures do nothing unless you `.await` or poll them"]
+    pub struct EnteredWhileRaised<F> {
+        #[pin]
+        future: F,
+        span: Span,
+        gate: Gate,
+    }
+}
+
+impl<F: Future> Future for EnteredWhileRaised<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<F::Output> {
+        let this = self.project();
+
+        if this.gate.is_raised() {
+            let _entered = this.span.enter();
+            this.future.poll(cx)
+        } else {
+            this.future.poll(cx)
+        }
+    }
+}
+
+impl Gate {
+    /// Wrap the `future` so that the given tracing `span` is entered around each poll of it while this gate is raised,
+    /// and not while it's lowered, so traces show the periods the work was gated off.
+    ///
+    /// This doesn't wait for the gate to be raised: the `future` is polled either way.
+    ///
+    /// This is only available with the `tracing` feature.
+    pub fn entered_span<F: Future>(&self, span: Span, future: F) -> EnteredWhileRaised<F> {
+        EnteredWhileRaised {
+            future,
+            span,
+            gate: self.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use tracing::{
+        span::{Attributes, Id, Record},
+        subscriber::with_default,
+        Event, Metadata, Subscriber,
+    };
+
+    /// Counts how many times any span is entered
+    #[derive(Clone, Default)]
+    struct CountEnters(Arc<AtomicUsize>);
+
+    impl Subscriber for CountEnters {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+        fn new_span(&self, _: &Attributes<'_>) -> Id {
+            Id::from_u64(1)
+        }
+        fn record(&self, _: &Id, _: &Record<'_>) {}
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+        fn event(&self, _: &Event<'_>) {}
+        fn enter(&self, _: &Id) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+        fn exit(&self, _: &Id) {}
+    }
+
+    /// Tests that the span is only entered for polls that happen while the gate is raised.
+    #[test]
+    fn enters_only_while_raised() {
+        let subscriber = CountEnters::default();
+        let enters = Arc::clone(&subscriber.0);
+
+        with_default(subscriber, || {
+            let (lever, gate) = crate::new_lowered();
+            let mut work = tokio_test::task::spawn(
+                gate.entered_span(tracing::info_span!("work"), std::future::pending::<()>()),
+            );
+
+            let _ = work.poll();
+            assert_eq!(enters.load(Ordering::Relaxed), 0);
+
+            lever.raise().unwrap();
+            let _ = work.poll();
+            assert_eq!(enters.load(Ordering::Relaxed), 1);
+        });
+    }
+}
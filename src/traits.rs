@@ -0,0 +1,177 @@
+//! Traits covering the core operations of [`Gate`] and [`Lever`],
+//! so that code can accept anything gate-like or lever-like
+//! (and tests can substitute fakes without a real gate and lever).
+//!
+//! Both traits are object safe, so they can be used as `&mut dyn GateLike` or `Box<dyn LeverLike>` too.
+//! With the `mockall` feature, [`mockall`](https://docs.rs/mockall) mocks of them
+//! (`MockGateLike` and `MockLeverLike`) are generated as well.
+
+use std::{future::Future, pin::Pin};
+
+use crate::{
+    BeforeGateDropped, Gate, GateDropped, Lever, LeverDroppedWhileLowered, LeverDroppedWhileRaised,
+};
+
+/// A future that is boxed so that it can be returned from the methods of an object safe trait
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// The operations of a [`Gate`]: checking if it's raised or lowered and waiting for it to be
+#[cfg_attr(feature = "mockall", mockall::automock)]
+pub trait GateLike {
+    /// See [`Gate::is_raised`]
+    fn is_raised(&self) -> bool;
+
+    /// See [`Gate::is_lowered`]
+    fn is_lowered(&self) -> bool;
+
+    /// See [`Gate::raised`]
+    fn raised(&mut self) -> BoxFuture<'_, Result<(), LeverDroppedWhileLowered>>;
+
+    /// See [`Gate::lowered`]
+    fn lowered(&mut self) -> BoxFuture<'_, Result<(), LeverDroppedWhileRaised>>;
+
+    /// See [`Gate::lever_was_dropped`]
+    fn lever_was_dropped(&self) -> bool;
+}
+
+/// The operations of a [`Lever`]: raising and lowering its gate and checking its state
+#[cfg_attr(feature = "mockall", mockall::automock)]
+pub trait LeverLike {
+    /// See [`Lever::raise`]
+    /// # Errors
+    /// See [`Lever::raise`]
+    fn raise(&self) -> Result<bool, GateDropped>;
+
+    /// See [`Lever::lower`]
+    /// # Errors
+    /// See [`Lever::lower`]
+    fn lower(&self) -> Result<bool, GateDropped>;
+
+    /// See [`Lever::is_raised`]
+    /// # Errors
+    /// See [`Lever::is_raised`]
+    fn is_raised(&self) -> Result<bool, BeforeGateDropped>;
+
+    /// See [`Lever::is_lowered`]
+    /// # Errors
+    /// See [`Lever::is_lowered`]
+    fn is_lowered(&self) -> Result<bool, BeforeGateDropped>;
+
+    /// See [`Lever::gate_was_dropped`]
+    fn gate_was_dropped(&self) -> bool;
+}
+
+impl GateLike for Gate {
+    fn is_raised(&self) -> bool {
+        Gate::is_raised(self)
+    }
+
+    fn is_lowered(&self) -> bool {
+        Gate::is_lowered(self)
+    }
+
+    fn raised(&mut self) -> BoxFuture<'_, Result<(), LeverDroppedWhileLowered>> {
+        Box::pin(Gate::raised(self))
+    }
+
+    fn lowered(&mut self) -> BoxFuture<'_, Result<(), LeverDroppedWhileRaised>> {
+        Box::pin(Gate::lowered(self))
+    }
+
+    fn lever_was_dropped(&self) -> bool {
+        Gate::lever_was_dropped(self)
+    }
+}
+
+impl LeverLike for Lever {
+    fn raise(&self) -> Result<bool, GateDropped> {
+        Lever::raise(self)
+    }
+
+    fn lower(&self) -> Result<bool, GateDropped> {
+        Lever::lower(self)
+    }
+
+    fn is_raised(&self) -> Result<bool, BeforeGateDropped> {
+        Lever::is_raised(self)
+    }
+
+    fn is_lowered(&self) -> Result<bool, BeforeGateDropped> {
+        Lever::is_lowered(self)
+    }
+
+    fn gate_was_dropped(&self) -> bool {
+        Lever::gate_was_dropped(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::future::ready;
+
+    use super::*;
+    use crate::new_lowered;
+
+    /// A gate that is always raised and whose lever never drops, without any real gate behind it
+    struct AlwaysRaised;
+
+    impl GateLike for AlwaysRaised {
+        fn is_raised(&self) -> bool {
+            true
+        }
+
+        fn is_lowered(&self) -> bool {
+            false
+        }
+
+        fn raised(&mut self) -> BoxFuture<'_, Result<(), LeverDroppedWhileLowered>> {
+            Box::pin(ready(Ok(())))
+        }
+
+        fn lowered(&mut self) -> BoxFuture<'_, Result<(), LeverDroppedWhileRaised>> {
+            Box::pin(std::future::pending())
+        }
+
+        fn lever_was_dropped(&self) -> bool {
+            false
+        }
+    }
+
+    /// Tests that code written against `dyn GateLike` works with both a real `Gate` and a fake.
+    #[test]
+    fn real_and_fake_gates_are_interchangeable() {
+        let (lever, gate) = new_lowered();
+        let lever: Box<dyn LeverLike> = Box::new(lever);
+
+        let mut gates: Vec<Box<dyn GateLike>> = vec![Box::new(gate), Box::new(AlwaysRaised)];
+
+        lever.raise().unwrap();
+
+        for gate in &mut gates {
+            assert!(gate.is_raised());
+            tokio_test::assert_ready_ok!(tokio_test::task::spawn(gate.raised()).poll());
+        }
+    }
+
+    /// Tests that the generated mocks can stand in for a gate and a lever,
+    /// including scripting what waiting on the gate does.
+    #[cfg(feature = "mockall")]
+    #[test]
+    fn mocks_can_be_scripted() {
+        let mut gate = MockGateLike::new();
+        gate.expect_is_raised().return_const(false);
+        gate.expect_raised()
+            .times(1)
+            .returning(|| Box::pin(ready(Err(LeverDroppedWhileLowered))));
+
+        let mut lever = MockLeverLike::new();
+        lever.expect_raise().times(1).returning(|| Err(GateDropped));
+
+        let gate: &mut dyn GateLike = &mut gate;
+        let lever: &dyn LeverLike = &lever;
+
+        assert!(lever.raise().is_err());
+        assert!(!gate.is_raised());
+        tokio_test::assert_ready_err!(tokio_test::task::spawn(gate.raised()).poll());
+    }
+}
@@ -0,0 +1,284 @@
+//! Two-phase raising, for coordinated cutovers where a half-raised state is dangerous:
+//! a raise is first proposed to every registered [`Participant`],
+//! and the gate is only raised once all of them acknowledge that they're ready.
+//!
+//! This module is only available with the `time` feature.
+
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
+
+use thiserror::Error;
+use tokio::sync::watch;
+
+use crate::{Gate, GateDropped, Lever};
+
+/// The proposal being voted on and the votes so far
+#[derive(Debug, Default)]
+struct Round {
+    /// The proposal currently waiting for acknowledgements, if any
+    proposal: Option<u64>,
+    /// The last proposal each participant (by id) acknowledged
+    acknowledged: BTreeMap<usize, Option<u64>>,
+    next_proposal: u64,
+    next_participant: usize,
+    coordinator_dropped: bool,
+}
+
+/// A proposed raise wasn't carried out
+#[derive(Debug, Error)]
+pub enum ProposeRaiseError {
+    /// Some participants (listed by [id](Participant::id)) didn't acknowledge the proposal in time,
+    /// so the gate was left as it was
+    #[error("participants {missing:?} did not acknowledge the raise in time")]
+    NotAcknowledged {
+        /// The ids of the participants that didn't acknowledge
+        missing: Vec<usize>,
+    },
+    /// Everyone acknowledged, but the gate was dropped
+    #[error(transparent)]
+    GateDropped(#[from] GateDropped),
+}
+
+/// The coordinator was dropped, so there will be no more proposals
+#[derive(Debug, Error)]
+#[error("coordinator was dropped")]
+pub struct CoordinatorDropped;
+
+/// A lever that only raises its gate once every registered [`Participant`] acknowledges the raise
+pub struct Coordinator {
+    lever: Lever,
+    round: Arc<watch::Sender<Round>>,
+}
+
+/// Someone who must acknowledge a proposed raise before the gate is raised
+///
+/// Dropping a participant unregisters it, so later proposals don't wait for it.
+pub struct Participant {
+    id: usize,
+    round: Arc<watch::Sender<Round>>,
+    receiver: watch::Receiver<Round>,
+}
+
+/// Withdraws the current proposal when dropped, even if proposing is cancelled partway through
+struct Withdraw<'a>(&'a watch::Sender<Round>);
+
+impl Drop for Withdraw<'_> {
+    fn drop(&mut self) {
+        self.0.send_modify(|round| round.proposal = None);
+    }
+}
+
+impl Coordinator {
+    /// Coordinate raising the given `lever` (it has no participants yet).
+    #[must_use]
+    pub fn new(lever: Lever) -> Self {
+        let (sender, _) = watch::channel(Round::default());
+
+        Self {
+            lever,
+            round: Arc::new(sender),
+        }
+    }
+
+    /// Register a new participant that future raises must be acknowledged by.
+    #[must_use]
+    pub fn participant(&self) -> Participant {
+        let mut id = 0;
+        self.round.send_modify(|round| {
+            id = round.next_participant;
+            round.next_participant += 1;
+            round.acknowledged.insert(id, None);
+        });
+
+        Participant {
+            id,
+            round: Arc::clone(&self.round),
+            receiver: self.round.subscribe(),
+        }
+    }
+
+    /// Propose raising the gate, and raise it once every participant acknowledges the proposal.
+    /// If there are no participants, the gate is raised right away.
+    /// # Errors
+    /// If some participants don't acknowledge within `timeout`, the proposal is withdrawn,
+    /// the gate is left as it was, and `Err(ProposeRaiseError::NotAcknowledged)` lists who was missing.
+    /// If the gate was dropped, `Err(ProposeRaiseError::GateDropped)` is returned.
+    /// # Cancel safety
+    /// This method is cancel safe: if it's cancelled, the proposal is withdrawn and the gate isn't raised.
+    pub async fn propose_raise(&mut self, timeout: Duration) -> Result<(), ProposeRaiseError> {
+        let mut proposal = 0;
+        self.round.send_modify(|round| {
+            proposal = round.next_proposal;
+            round.next_proposal += 1;
+            round.proposal = Some(proposal);
+        });
+        let withdraw = Withdraw(&self.round);
+
+        let mut receiver = self.round.subscribe();
+        let acknowledged = tokio::time::timeout(
+            timeout,
+            receiver.wait_for(|round| {
+                round
+                    .acknowledged
+                    .values()
+                    .all(|acknowledged| *acknowledged == Some(proposal))
+            }),
+        )
+        .await
+        .is_ok();
+
+        let missing = self
+            .round
+            .borrow()
+            .acknowledged
+            .iter()
+            .filter(|(_, acknowledged)| **acknowledged != Some(proposal))
+            .map(|(id, _)| *id)
+            .collect();
+        drop(withdraw);
+
+        if acknowledged {
+            self.lever.raise()?;
+            Ok(())
+        } else {
+            Err(ProposeRaiseError::NotAcknowledged { missing })
+        }
+    }
+
+    /// Lower the gate right away (lowering doesn't need acknowledgements),
+    /// returning whether it was raised before.
+    /// # Errors
+    /// If the gate was dropped, an `Err` is returned.
+    pub fn lower(&self) -> Result<bool, GateDropped> {
+        self.lever.lower()
+    }
+
+    /// Returns the lever being coordinated.
+    #[must_use]
+    pub fn lever(&self) -> &Lever {
+        &self.lever
+    }
+}
+
+impl Drop for Coordinator {
+    fn drop(&mut self) {
+        self.round
+            .send_modify(|round| round.coordinator_dropped = true);
+    }
+}
+
+impl Participant {
+    /// Returns the id of this participant, as reported in [`ProposeRaiseError::NotAcknowledged`].
+    #[must_use]
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    /// Wait until a raise is proposed that this participant hasn't acknowledged yet.
+    /// # Errors
+    /// If the coordinator is dropped first, an `Err` is returned.
+    /// # Cancel safety
+    /// This method is cancel safe.
+    pub async fn proposed(&mut self) -> Result<(), CoordinatorDropped> {
+        let id = self.id;
+        let round = self
+            .receiver
+            .wait_for(|round| {
+                round.coordinator_dropped
+                    || round
+                        .proposal
+                        .is_some_and(|proposal| round.acknowledged[&id] != Some(proposal))
+            })
+            .await
+            .map_err(|_| CoordinatorDropped)?;
+
+        if round.coordinator_dropped {
+            Err(CoordinatorDropped)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Acknowledge the current proposal, if there is one.
+    pub fn acknowledge(&self) {
+        self.round.send_if_modified(|round| match round.proposal {
+            Some(proposal) => {
+                round.acknowledged.insert(self.id, Some(proposal)) != Some(Some(proposal))
+            }
+            None => false,
+        });
+    }
+}
+
+impl Drop for Participant {
+    fn drop(&mut self) {
+        self.round.send_modify(|round| {
+            round.acknowledged.remove(&self.id);
+        });
+    }
+}
+
+/// Create a lowered gate that is only raised once every participant registered with the returned [`Coordinator`] acknowledges the raise.
+#[must_use]
+pub fn new() -> (Coordinator, Gate) {
+    let (lever, gate) = crate::new_lowered();
+
+    (Coordinator::new(lever), gate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests that the gate is only raised after every participant acknowledges.
+    #[tokio::test(start_paused = true)]
+    async fn raises_once_everyone_acknowledges() {
+        let (mut coordinator, gate) = new();
+
+        for _ in 0..3 {
+            let mut participant = coordinator.participant();
+            let gate = gate.clone();
+            tokio::spawn(async move {
+                participant.proposed().await.unwrap();
+                assert!(gate.is_lowered());
+                participant.acknowledge();
+                participant.proposed().await
+            });
+        }
+
+        coordinator
+            .propose_raise(Duration::from_secs(1))
+            .await
+            .unwrap();
+        assert!(gate.is_raised());
+    }
+
+    /// Tests that a participant that doesn't acknowledge is reported and the gate stays lowered.
+    #[tokio::test(start_paused = true)]
+    async fn reports_missing_participants() {
+        let (mut coordinator, gate) = new();
+
+        let mut ready = coordinator.participant();
+        let silent = coordinator.participant();
+        tokio::spawn(async move {
+            ready.proposed().await.unwrap();
+            ready.acknowledge();
+            ready.proposed().await
+        });
+
+        let error = coordinator
+            .propose_raise(Duration::from_secs(1))
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(error, ProposeRaiseError::NotAcknowledged { missing } if missing == [silent.id()])
+        );
+        assert!(gate.is_lowered());
+
+        drop(silent);
+        coordinator
+            .propose_raise(Duration::from_secs(1))
+            .await
+            .unwrap();
+        assert!(gate.is_raised());
+    }
+}
@@ -0,0 +1,145 @@
+//! Gates over states of your own, like a `Starting`/`Ready`/`Stopping` enum,
+//! for when raised and lowered aren't enough:
+//! the [`StateLever`] moves between the states and waiters [`wait_for_state`](StateGate::wait_for_state) one.
+//!
+//! This is [`crate::data`] specialized to states that are compared for equality,
+//! so waiters are only woken when the state actually changes.
+
+use crate::data::{self, DataGate, DataGatesDropped, DataLever, DataLeverDropped};
+
+/// Moves the gate between states and wakes the [`StateGate`]s waiting on it
+pub struct StateLever<S> {
+    lever: DataLever<S>,
+}
+
+/// Waits for the gate to be in a given state
+pub struct StateGate<S> {
+    gate: DataGate<S>,
+}
+
+impl<S> Clone for StateGate<S> {
+    fn clone(&self) -> Self {
+        Self {
+            gate: self.gate.clone(),
+        }
+    }
+}
+
+impl<S: Clone + Eq> StateLever<S> {
+    /// Move the gate to the given `state`, returning the state it was in before.
+    /// Waiters are only woken if that's a different state.
+    /// # Errors
+    /// If every gate was dropped, an `Err` is returned (but the state is still changed).
+    pub fn set(&self, state: S) -> Result<S, DataGatesDropped> {
+        let mut previous = None;
+        let result = self.lever.update_if(|current| {
+            let changed = *current != state;
+            previous = Some(std::mem::replace(current, state));
+            changed
+        });
+
+        result.map(|()| previous.expect("the update was run"))
+    }
+
+    /// Returns the state the gate is in.
+    #[must_use]
+    pub fn get(&self) -> S {
+        self.lever.borrow().clone()
+    }
+}
+
+impl<S: Clone + Eq> StateGate<S> {
+    /// Returns the state the gate is in.
+    #[must_use]
+    pub fn get(&self) -> S {
+        self.gate.borrow().clone()
+    }
+
+    /// Wait until the gate is in the given `state` (returning immediately if it already is).
+    /// # Errors
+    /// If the lever is dropped while the gate is in some other state, an `Err` is returned.
+    /// # Cancel safety
+    /// This method is cancel safe.
+    pub async fn wait_for_state(&mut self, state: S) -> Result<(), DataLeverDropped> {
+        self.gate
+            .wait_until(|current| *current == state)
+            .await
+            .map(drop)
+    }
+
+    /// Wait until the gate's state satisfies the given `condition`
+    /// (like being in any one of several states), returning that state.
+    /// # Errors
+    /// If the lever is dropped while the condition isn't satisfied, an `Err` is returned.
+    /// # Cancel safety
+    /// This method is cancel safe.
+    pub async fn wait_for(
+        &mut self,
+        condition: impl FnMut(&S) -> bool,
+    ) -> Result<S, DataLeverDropped> {
+        self.gate
+            .wait_until(condition)
+            .await
+            .map(|state| state.clone())
+    }
+
+    /// Returns whether the lever has been dropped (so the state will never change again).
+    #[must_use]
+    pub fn lever_was_dropped(&self) -> bool {
+        self.gate.lever_was_dropped()
+    }
+}
+
+/// Create a lever and gate starting in the `initial` state.
+#[must_use]
+pub fn new<S: Clone + Eq>(initial: S) -> (StateLever<S>, StateGate<S>) {
+    let (lever, gate) = data::new(initial);
+
+    (StateLever { lever }, StateGate { gate })
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio_test::{assert_pending, assert_ready, assert_ready_err, task};
+
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    enum Phase {
+        Starting,
+        Ready,
+        Stopping,
+    }
+
+    /// Tests that waiting for a state finishes once the lever moves the gate to it.
+    #[test]
+    fn waits_for_state() {
+        let (lever, gate) = new(Phase::Starting);
+        let mut waiter = gate.clone();
+
+        let mut waiting = task::spawn(waiter.wait_for_state(Phase::Ready));
+        assert_pending!(waiting.poll());
+
+        assert_eq!(lever.set(Phase::Stopping).unwrap(), Phase::Starting);
+        assert_pending!(waiting.poll());
+
+        lever.set(Phase::Ready).unwrap();
+        assert_ready!(waiting.poll()).unwrap();
+        assert_eq!(gate.get(), Phase::Ready);
+    }
+
+    /// Tests that setting the state the gate is already in doesn't wake waiters.
+    #[test]
+    fn same_state_doesnt_wake() {
+        let (lever, mut gate) = new(Phase::Starting);
+
+        let mut waiting = task::spawn(gate.wait_for(|phase| *phase != Phase::Starting));
+        assert_pending!(waiting.poll());
+
+        lever.set(Phase::Starting).unwrap();
+        assert!(!waiting.is_woken());
+
+        drop(lever);
+        assert_ready_err!(waiting.poll());
+    }
+}
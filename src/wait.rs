@@ -0,0 +1,186 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use pin_project_lite::pin_project;
+use tokio::sync::futures::OwnedNotified;
+
+use crate::{Gate, Gateway, LeverDroppedWhileLowered, LeverDroppedWhileRaised, Lowered, Raised};
+
+/// Poll for the `gate` to be in the `target` state, keeping its registration for wakeups in `notified` between polls.
+/// Returns `Poll::Ready(true)` once it is, or `Poll::Ready(false)` if the lever was dropped before that.
+pub(crate) fn poll_gateway(
+    gate: &mut Gate,
+    mut notified: Pin<&mut Option<OwnedNotified>>,
+    target: Gateway,
+    cx: &mut Context<'_>,
+) -> Poll<bool> {
+    loop {
+        if notified.is_none() {
+            let notify = gate.receiver.borrow().notify.clone();
+            notified.set(Some(notify.notified_owned()));
+        }
+
+        let mut waiting = notified
+            .as_mut()
+            .as_pin_mut()
+            .expect("the notification was just set up");
+        // Listen before checking, so that a change right after the check still wakes the task
+        waiting.as_mut().enable();
+
+        if gate.receiver.borrow_and_update().gateway == target {
+            notified.set(None);
+            return Poll::Ready(true);
+        }
+        if gate.lever_was_dropped() {
+            notified.set(None);
+            return Poll::Ready(false);
+        }
+
+        ready!(waiting.poll(cx));
+        notified.set(None);
+    }
+}
+
+pin_project! {
+    /// The future of [`Gate::raised`] (and of awaiting `&mut gate`), which waits until the gate is raised
+    ///
+    /// Unlike the future of an `async fn`, this one has a name,
+    /// so it can be kept in a struct or used as an associated type.
+    /// It's `Send` and `Sync`, so it can be held across `.await`s in spawned tasks,
+    /// but it isn't `Unpin`: to poll it by hand, pin it first (like with [`std::pin::pin!`] or [`Box::pin`]).
+    #[must_use = "futures do nothing unless you `.await` or poll them"]
+    pub struct WaitRaised<'a> {
+        gate: &'a mut Gate,
+        #[pin]
+        notified: Option<OwnedNotified>,
+    }
+}
+
+impl<'a> WaitRaised<'a> {
+    pub(crate) fn new(gate: &'a mut Gate) -> Self {
+        Self {
+            gate,
+            notified: None,
+        }
+    }
+}
+
+impl Future for WaitRaised<'_> {
+    type Output = Result<(), LeverDroppedWhileLowered>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+
+        poll_gateway(this.gate, this.notified, Raised, cx).map(|raised| {
+            if raised {
+                Ok(())
+            } else {
+                Err(LeverDroppedWhileLowered)
+            }
+        })
+    }
+}
+
+pin_project! {
+    /// The future of [`Gate::lowered`], which waits until the gate is lowered
+    ///
+    /// Like [`WaitRaised`], this can be named, and it's `Send` and `Sync` but not `Unpin`.
+    #[must_use = "futures do nothing unless you `.await` or poll them"]
+    pub struct WaitLowered<'a> {
+        gate: &'a mut Gate,
+        #[pin]
+        notified: Option<OwnedNotified>,
+    }
+}
+
+impl<'a> WaitLowered<'a> {
+    pub(crate) fn new(gate: &'a mut Gate) -> Self {
+        Self {
+            gate,
+            notified: None,
+        }
+    }
+}
+
+impl Future for WaitLowered<'_> {
+    type Output = Result<(), LeverDroppedWhileRaised>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+
+        poll_gateway(this.gate, this.notified, Lowered, cx).map(|lowered| {
+            if lowered {
+                Ok(())
+            } else {
+                Err(LeverDroppedWhileRaised)
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::future::IntoFuture;
+
+    use tokio_test::{assert_pending, assert_ready_err, assert_ready_ok, task};
+
+    use crate::{new_lowered, new_raised};
+
+    /// Tests that awaiting a gate finishes once it's raised, and fails once the lever is dropped while lowered.
+    #[test]
+    fn waits_until_raised() {
+        let (lever, mut gate) = new_lowered();
+
+        let mut waiting = task::spawn((&mut gate).into_future());
+        assert_pending!(waiting.poll());
+
+        lever.raise().unwrap();
+        assert!(waiting.is_woken());
+        assert_ready_ok!(waiting.poll());
+        drop(waiting);
+
+        lever.lower().unwrap();
+        let mut waiting = task::spawn((&mut gate).into_future());
+        assert_pending!(waiting.poll());
+
+        drop(lever);
+        assert!(waiting.is_woken());
+        assert_ready_err!(waiting.poll());
+    }
+
+    /// Tests that waiting for a lower finishes once the gate is lowered,
+    /// and fails once the lever is dropped while raised.
+    #[test]
+    fn waits_until_lowered() {
+        let (lever, mut gate) = new_raised();
+
+        let mut waiting = task::spawn(gate.lowered());
+        assert_pending!(waiting.poll());
+
+        lever.lower().unwrap();
+        assert!(waiting.is_woken());
+        assert_ready_ok!(waiting.poll());
+        drop(waiting);
+
+        lever.raise().unwrap();
+        let mut waiting = task::spawn(gate.lowered());
+        assert_pending!(waiting.poll());
+
+        drop(lever);
+        assert!(waiting.is_woken());
+        assert_ready_err!(waiting.poll());
+    }
+
+    /// Tests that the waits are `Send` and `Sync`, so they can be held across `.await`s in spawned tasks.
+    #[test]
+    fn waits_are_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>(_: &T) {}
+
+        let (_lever, mut gate) = new_lowered();
+        assert_send_sync(&gate.raised());
+        assert_send_sync(&gate.lowered());
+    }
+}
@@ -0,0 +1,94 @@
+use std::sync::{Arc, Weak};
+
+use crate::{Gate, Lever, LeverShared};
+
+/// A reference to a lever that doesn't keep it alive
+/// (so the gate still sees the lever as dropped once every [`Lever`] is dropped)
+///
+/// Created with [`Lever::downgrade`].
+#[derive(Clone)]
+pub struct WeakLever {
+    sender: Weak<LeverShared>,
+}
+
+impl WeakLever {
+    /// Returns the lever, or `None` if every lever was dropped.
+    #[must_use]
+    pub fn upgrade(&self) -> Option<Lever> {
+        self.sender.upgrade().map(|sender| Lever { sender })
+    }
+}
+
+/// A reference to a gate that doesn't keep it alive
+/// (so the lever still sees the gate as dropped once every [`Gate`] is dropped)
+///
+/// Created with [`Lever::weak_gate`].
+#[derive(Clone)]
+pub struct WeakGate {
+    sender: Weak<LeverShared>,
+}
+
+impl WeakGate {
+    /// Returns a new gate for the same lever, or `None` if every gate was dropped.
+    ///
+    /// This also returns `None` once every lever was dropped,
+    /// since that's what a weak gate uses to reach the gates.
+    #[must_use]
+    pub fn upgrade(&self) -> Option<Gate> {
+        let sender = self.sender.upgrade()?;
+
+        if sender.is_closed() {
+            None
+        } else {
+            Some(Gate {
+                receiver: sender.subscribe(),
+            })
+        }
+    }
+}
+
+impl Lever {
+    /// Returns a reference to this lever that doesn't keep it alive.
+    #[must_use]
+    pub fn downgrade(&self) -> WeakLever {
+        WeakLever {
+            sender: Arc::downgrade(&self.sender),
+        }
+    }
+
+    /// Returns a reference to this lever's gates that doesn't keep them alive.
+    ///
+    /// (This is on the lever rather than on [`Gate`] since it needs a way to create new gates,
+    /// which only the lever has.)
+    #[must_use]
+    pub fn weak_gate(&self) -> WeakGate {
+        WeakGate {
+            sender: Arc::downgrade(&self.sender),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::new_lowered;
+
+    /// Tests that weak handles don't keep anything alive and upgrade while it's alive.
+    #[test]
+    fn weak_handles_upgrade_while_alive() {
+        let (lever, gate) = new_lowered();
+        let weak_lever = lever.downgrade();
+        let weak_gate = lever.weak_gate();
+
+        weak_lever.upgrade().unwrap().raise().unwrap();
+        assert!(weak_gate.upgrade().unwrap().is_raised());
+
+        drop(gate);
+        assert!(lever.gate_was_dropped());
+        assert!(weak_gate.upgrade().is_none());
+
+        let gate = lever.subscribe();
+        drop(lever);
+        assert!(gate.lever_was_dropped());
+        assert!(weak_lever.upgrade().is_none());
+    }
+}
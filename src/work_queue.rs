@@ -0,0 +1,170 @@
+use std::{future::Future, sync::Arc};
+
+use thiserror::Error;
+use tokio::sync::{mpsc, Semaphore};
+
+use crate::{traits::BoxFuture, Gate};
+
+/// The work queue stopped taking work, because its gate's lever was dropped while the gate was lowered
+#[derive(Debug, Error)]
+#[error("work queue was closed")]
+pub struct WorkQueueClosed;
+
+/// A queue of work that only runs while a gate is raised:
+/// work submitted while the gate is lowered waits in the queue,
+/// and is started (in the order it was submitted) as soon as the gate is raised.
+///
+/// At most a fixed number of pieces of work run at once.
+/// Work that already started keeps running if the gate is lowered again.
+/// Dropping the queue doesn't cancel work that was already submitted.
+///
+/// If the gate's lever is dropped while the gate is lowered, the queue closes:
+/// work still waiting in it is dropped without running, and later submits fail.
+///
+/// This is only available with the `rt` feature, and must be created inside a Tokio runtime.
+pub struct GatedWorkQueue {
+    sender: mpsc::UnboundedSender<BoxFuture<'static, ()>>,
+}
+
+impl GatedWorkQueue {
+    /// Start a queue that runs work while the given `gate` is raised,
+    /// running at most `concurrency` pieces of work at once.
+    /// # Panics
+    /// Panics if `concurrency` is 0 (since no work could ever run)
+    /// or if called outside of a Tokio runtime.
+    #[must_use]
+    pub fn new(mut gate: Gate, concurrency: usize) -> Self {
+        assert!(
+            concurrency > 0,
+            "a work queue must be able to run some work"
+        );
+
+        let (sender, mut receiver) = mpsc::unbounded_channel::<BoxFuture<'static, ()>>();
+        let permits = Arc::new(Semaphore::new(concurrency));
+
+        tokio::spawn(async move {
+            while let Some(work) = receiver.recv().await {
+                let Ok(permit) = permits.clone().acquire_owned().await else {
+                    break;
+                };
+
+                // Only checked once there's room to run the work, so it starts right away while the gate is raised
+                if gate.raised().await.is_err() {
+                    // Dropping the receiver drops the work still in the queue (and closes it to new work)
+                    break;
+                }
+
+                tokio::spawn(async move {
+                    work.await;
+                    drop(permit);
+                });
+            }
+        });
+
+        Self { sender }
+    }
+
+    /// Add the given `work` to the end of the queue.
+    /// # Errors
+    /// If the gate's lever was dropped while the gate was lowered, the work can never run, so an `Err` is returned.
+    /// Work that was accepted before then is dropped without running.
+    pub fn submit(
+        &self,
+        work: impl Future<Output = ()> + Send + 'static,
+    ) -> Result<(), WorkQueueClosed> {
+        self.sender
+            .send(Box::pin(work))
+            .map_err(|_| WorkQueueClosed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::oneshot;
+
+    use super::*;
+    use crate::{new_lowered, new_raised};
+
+    /// Tests that work waits for the gate to be raised, then runs in order.
+    #[tokio::test]
+    async fn runs_in_order_once_raised() {
+        let (lever, gate) = new_lowered();
+        let queue = GatedWorkQueue::new(gate, 1);
+        let (done_sender, mut done_receiver) = mpsc::unbounded_channel();
+
+        for index in 0..3 {
+            let done_sender = done_sender.clone();
+            queue
+                .submit(async move {
+                    done_sender.send(index).unwrap();
+                })
+                .unwrap();
+        }
+
+        tokio::task::yield_now().await;
+        assert!(done_receiver.try_recv().is_err());
+
+        lever.raise().unwrap();
+
+        for index in 0..3 {
+            assert_eq!(done_receiver.recv().await, Some(index));
+        }
+    }
+
+    /// Tests that the queue stops taking work once the lever is dropped while the gate is lowered,
+    /// and drops the work it already accepted without running it.
+    #[tokio::test]
+    async fn closes_when_lever_dropped_while_lowered() {
+        let (lever, gate) = new_lowered();
+        let queue = GatedWorkQueue::new(gate, 1);
+        let (ran, mut accepted) = oneshot::channel();
+
+        queue
+            .submit(async move {
+                ran.send(()).unwrap();
+            })
+            .unwrap();
+        drop(lever);
+
+        while queue.submit(async {}).is_ok() {
+            tokio::task::yield_now().await;
+        }
+        assert_eq!(
+            accepted.try_recv(),
+            Err(oneshot::error::TryRecvError::Closed)
+        );
+    }
+
+    /// Tests that work waiting for room to run doesn't start if the gate was lowered in the meantime.
+    #[tokio::test]
+    async fn waits_for_raise_once_there_is_room() {
+        let (lever, gate) = new_raised();
+        let queue = GatedWorkQueue::new(gate, 1);
+        let (finish, finished) = oneshot::channel::<()>();
+        let (started_sender, mut started) = mpsc::unbounded_channel();
+
+        let first_started = started_sender.clone();
+        queue
+            .submit(async move {
+                first_started.send(0).unwrap();
+                let _ = finished.await;
+            })
+            .unwrap();
+        queue
+            .submit(async move {
+                started_sender.send(1).unwrap();
+            })
+            .unwrap();
+        assert_eq!(started.recv().await, Some(0));
+
+        lever.lower().unwrap();
+        finish.send(()).unwrap();
+        for _ in 0..10 {
+            tokio::task::yield_now().await;
+        }
+        assert!(started.try_recv().is_err());
+
+        lever.raise().unwrap();
+        assert_eq!(started.recv().await, Some(1));
+    }
+}
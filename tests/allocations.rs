@@ -0,0 +1,124 @@
+//! Checks that waiting on a gate doesn't allocate,
+//! by counting allocations made on the current thread with a wrapper around the system allocator.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+    future::{Future, IntoFuture},
+    pin::pin,
+    sync::Arc,
+    task::{Context, Wake, Waker},
+};
+
+use async_gate::{new_lowered, new_raised, PollGate};
+
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|allocations| allocations.set(allocations.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Returns how many allocations running `f` made on this thread
+fn allocations_during(f: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.with(Cell::get);
+    f();
+    ALLOCATIONS.with(Cell::get) - before
+}
+
+struct NoopWaker;
+
+impl Wake for NoopWaker {
+    fn wake(self: Arc<Self>) {}
+}
+
+/// Tests that waiting for a raise (parking, being woken, and resolving) doesn't allocate.
+#[test]
+fn raised_does_not_allocate() {
+    let (lever, mut gate) = new_lowered();
+    let waker = Waker::from(Arc::new(NoopWaker));
+    let mut context = Context::from_waker(&waker);
+
+    let allocations = allocations_during(|| {
+        let mut waiting = pin!(gate.raised());
+        assert!(waiting.as_mut().poll(&mut context).is_pending());
+
+        lever.raise().unwrap();
+
+        assert!(waiting.as_mut().poll(&mut context).is_ready());
+    });
+
+    assert_eq!(allocations, 0);
+}
+
+/// Tests that waiting for a lower (parking, being woken, and resolving) doesn't allocate.
+#[test]
+fn lowered_does_not_allocate() {
+    let (lever, mut gate) = new_raised();
+    let waker = Waker::from(Arc::new(NoopWaker));
+    let mut context = Context::from_waker(&waker);
+
+    let allocations = allocations_during(|| {
+        let mut waiting = pin!(gate.lowered());
+        assert!(waiting.as_mut().poll(&mut context).is_pending());
+
+        lever.lower().unwrap();
+
+        assert!(waiting.as_mut().poll(&mut context).is_ready());
+    });
+
+    assert_eq!(allocations, 0);
+}
+
+/// Tests that awaiting `&mut gate` (parking, being woken, and resolving) doesn't allocate.
+#[test]
+fn into_future_does_not_allocate() {
+    let (lever, mut gate) = new_lowered();
+    let waker = Waker::from(Arc::new(NoopWaker));
+    let mut context = Context::from_waker(&waker);
+
+    let allocations = allocations_during(|| {
+        let mut waiting = pin!((&mut gate).into_future());
+        assert!(waiting.as_mut().poll(&mut context).is_pending());
+
+        lever.raise().unwrap();
+
+        assert!(waiting.as_mut().poll(&mut context).is_ready());
+    });
+
+    assert_eq!(allocations, 0);
+}
+
+/// Tests that polling a `PollGate` (parking, being woken, and resolving, for both states) doesn't allocate.
+#[test]
+fn poll_gate_does_not_allocate() {
+    let (lever, gate) = new_lowered();
+    let mut gate = pin!(PollGate::new(gate));
+    let waker = Waker::from(Arc::new(NoopWaker));
+    let mut context = Context::from_waker(&waker);
+
+    let allocations = allocations_during(|| {
+        assert!(gate.as_mut().poll_raised(&mut context).is_pending());
+        lever.raise().unwrap();
+        assert!(gate.as_mut().poll_raised(&mut context).is_ready());
+
+        assert!(gate.as_mut().poll_lowered(&mut context).is_pending());
+        lever.lower().unwrap();
+        assert!(gate.as_mut().poll_lowered(&mut context).is_ready());
+    });
+
+    assert_eq!(allocations, 0);
+}
@@ -0,0 +1,84 @@
+//! Checks that waiting on a gate works without a Tokio runtime:
+//! driven by a minimal executor that parks the thread until it's woken
+//! (with the lever used from a plain thread) and by `futures-lite` (as used by `smol`).
+
+use std::{
+    future::Future,
+    pin::pin,
+    sync::Arc,
+    task::{Context, Poll, Wake, Waker},
+    thread::{self, Thread},
+    time::Duration,
+};
+
+use async_gate::new_lowered;
+
+struct Unpark(Thread);
+
+impl Wake for Unpark {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// Run the `future` to completion on this thread, without any async runtime
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let waker = Waker::from(Arc::new(Unpark(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
+/// Tests that a gate can be waited on with a non-Tokio executor while a plain thread moves the lever.
+#[test]
+fn waits_without_tokio_runtime() {
+    let (lever, mut gate) = new_lowered();
+
+    let flipping = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(10));
+        lever.raise().unwrap();
+        thread::sleep(Duration::from_millis(10));
+        lever.lower().unwrap();
+    });
+
+    block_on(async {
+        gate.raised().await.unwrap();
+        gate.lowered().await.unwrap();
+    });
+
+    flipping.join().unwrap();
+}
+
+/// Tests that gate waits compose with `futures-lite` combinators and run on its executor.
+#[test]
+fn composes_with_futures_lite() {
+    let (lever, mut gate) = new_lowered();
+    let (other_lever, mut other_gate) = new_lowered();
+
+    let raised_first = futures_lite::future::block_on(futures_lite::future::or(
+        async {
+            gate.raised().await.unwrap();
+            1
+        },
+        async {
+            other_lever.raise().unwrap();
+            other_gate.raised().await.unwrap();
+            2
+        },
+    ));
+    assert_eq!(raised_first, 2);
+
+    lever.raise().unwrap();
+    let (raised, other_raised) = futures_lite::future::block_on(futures_lite::future::zip(
+        gate.raised(),
+        other_gate.raised(),
+    ));
+    raised.unwrap();
+    other_raised.unwrap();
+}
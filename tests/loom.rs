@@ -0,0 +1,35 @@
+//! Model checks [`AtomicGateway`](async_gate::AtomicGateway) under [`loom`],
+//! exploring the interleavings of concurrent operations on it.
+//!
+//! Run with `RUSTFLAGS="--cfg loom" cargo test --release --test loom`.
+#![cfg(loom)]
+
+use async_gate::{AtomicGateway, Lowered, Raised};
+use loom::{
+    sync::{atomic::Ordering, Arc},
+    thread,
+};
+
+/// Tests that when two threads race to raise an `AtomicGateway` with `compare_exchange`,
+/// exactly one of them wins.
+#[test]
+fn atomic_gateway_compare_exchange_has_one_winner() {
+    loom::model(|| {
+        let atomic = Arc::new(AtomicGateway::new(Lowered));
+
+        let other_atomic = atomic.clone();
+        let other = thread::spawn(move || {
+            other_atomic
+                .compare_exchange(Lowered, Raised, Ordering::AcqRel, Ordering::Acquire)
+                .is_ok()
+        });
+
+        let won = atomic
+            .compare_exchange(Lowered, Raised, Ordering::AcqRel, Ordering::Acquire)
+            .is_ok();
+        let other_won = other.join().unwrap();
+
+        assert!(won != other_won);
+        assert_eq!(atomic.load(Ordering::Acquire), Raised);
+    });
+}